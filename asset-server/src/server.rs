@@ -0,0 +1,116 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+#[derive(Clone, Debug)]
+pub enum AssetSource {
+    Directory(PathBuf),
+    Embedded(&'static [(&'static str, &'static [u8])]),
+}
+
+#[macro_export]
+macro_rules! embed_assets {
+    ($root:literal, $($path:literal),+ $(,)?) => {{
+        const FILES: &[(&str, &[u8])] = &[$(($path, include_bytes!(concat!($root, "/", $path)))),+];
+        $crate::AssetSource::Embedded(FILES)
+    }};
+}
+
+type Events = Receiver<notify::Result<notify::Event>>;
+
+pub struct AssetServer {
+    source: AssetSource,
+    loaded: HashSet<PathBuf>,
+    watcher: Option<(RecommendedWatcher, Events)>,
+}
+
+impl AssetServer {
+    pub fn new(source: AssetSource) -> Self {
+        let mut server = AssetServer {
+            source,
+            loaded: HashSet::new(),
+            watcher: None,
+        };
+        if let AssetSource::Directory(root) = &server.source {
+            match Self::watch(root) {
+                Ok((root, watcher, events)) => {
+                    server.source = AssetSource::Directory(root);
+                    server.watcher = Some((watcher, events));
+                }
+                Err(e) => log::warn!("Couldn't watch {} for changes: {}", root.display(), e),
+            }
+        }
+        server
+    }
+
+    fn watch(root: &Path) -> notify::Result<(PathBuf, RecommendedWatcher, Events)> {
+        let root = root.canonicalize()?;
+        let (events_tx, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(events_tx)?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+        Ok((root, watcher, events))
+    }
+
+    pub fn source(&self) -> &AssetSource {
+        &self.source
+    }
+
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> std::io::Result<Cow<'static, [u8]>> {
+        let path = path.as_ref();
+        let data = match &self.source {
+            AssetSource::Directory(root) => Cow::Owned(std::fs::read(root.join(path))?),
+            AssetSource::Embedded(files) => files
+                .iter()
+                .find(|(name, _)| Path::new(name) == path)
+                .map(|(_, data)| Cow::Borrowed(*data))
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::NotFound,
+                        format!("{} is not embedded.", path.display()),
+                    )
+                })?,
+        };
+        self.loaded.insert(path.to_path_buf());
+        Ok(data)
+    }
+
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let (root, events) = match (&self.source, &self.watcher) {
+            (AssetSource::Directory(root), Some((_, events))) => (root, events),
+            _ => return Vec::new(),
+        };
+        let mut changed = Vec::new();
+        for event in events.try_iter().flatten() {
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                continue;
+            }
+            for path in event.paths {
+                if let Ok(path) = path.strip_prefix(root) {
+                    if self.loaded.contains(path) && !changed.iter().any(|p| p == path) {
+                        changed.push(path.to_path_buf());
+                    }
+                }
+            }
+        }
+        changed
+    }
+}
@@ -0,0 +1,80 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use asset_server::*;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("asset-server-{}-{}", name, std::process::id()));
+    _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("gen")).unwrap();
+    dir
+}
+
+fn wait_for_changes(assets: &mut AssetServer) -> Vec<PathBuf> {
+    let start = Instant::now();
+    let mut changed = Vec::new();
+    while start.elapsed() < Duration::from_secs(5) {
+        changed.extend(assets.changed());
+        if !changed.is_empty() {
+            // Let the rest of the events for this write arrive.
+            std::thread::sleep(Duration::from_millis(100));
+            changed.extend(assets.changed());
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    changed.dedup();
+    changed
+}
+
+#[test]
+fn loads_from_directory() {
+    let dir = temp_dir("load");
+    std::fs::write(dir.join("gen/a.bin"), [1, 2, 3]).unwrap();
+    let mut assets = AssetServer::new(AssetSource::Directory(dir.clone()));
+    assert_eq!(&*assets.load("gen/a.bin").unwrap(), &[1, 2, 3]);
+    let missing = assets.load("gen/missing.bin").unwrap_err();
+    assert_eq!(missing.kind(), ErrorKind::NotFound);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn loads_embedded_files() {
+    let mut assets = AssetServer::new(embed_assets!("../../assets", "font.ron"));
+    let font = assets.load("font.ron").unwrap();
+    assert_eq!(&*font, std::fs::read("../assets/font.ron").unwrap());
+    let missing = assets.load("tiles.ron").unwrap_err();
+    assert_eq!(missing.kind(), ErrorKind::NotFound);
+    assert!(assets.changed().is_empty());
+}
+
+#[test]
+fn reports_changes_to_loaded_files_only() {
+    let dir = temp_dir("changed");
+    std::fs::write(dir.join("gen/a.bin"), [1]).unwrap();
+    std::fs::write(dir.join("gen/b.bin"), [1]).unwrap();
+    let mut assets = AssetServer::new(AssetSource::Directory(dir.clone()));
+    assets.load("gen/a.bin").unwrap();
+    assert!(assets.changed().is_empty());
+
+    std::fs::write(dir.join("gen/b.bin"), [2]).unwrap();
+    std::fs::write(dir.join("gen/a.bin"), [2]).unwrap();
+    assert_eq!(wait_for_changes(&mut assets), [Path::new("gen/a.bin")]);
+    assert_eq!(&*assets.load("gen/a.bin").unwrap(), &[2]);
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -12,7 +12,13 @@
  * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
  */
 
-use rodio::Source;
+use std::sync::Arc;
+
+use rodio::{OutputStream, OutputStreamHandle, Source};
+
+use asset_server::*;
+
+pub const NUM_SOUNDS: usize = 1;
 
 #[repr(usize)]
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
@@ -20,11 +26,65 @@ pub enum Sound {
     TestSound1,
 }
 
-const SOUND_DATA: [&[u8]; 1] = [include_bytes!("../../assets/gen/test-sound1.wav")];
+impl Sound {
+    pub const ALL: [Sound; NUM_SOUNDS] = [Sound::TestSound1];
+
+    pub fn path(self) -> &'static str {
+        match self {
+            Sound::TestSound1 => "gen/test-sound1.wav",
+        }
+    }
+}
+
+pub struct Sounds {
+    assets: AssetServer,
+    data: Vec<Arc<[u8]>>,
+    output: Option<(OutputStream, OutputStreamHandle)>,
+}
+
+impl Sounds {
+    pub fn new(mut assets: AssetServer) -> std::io::Result<Self> {
+        let data = Sound::ALL
+            .iter()
+            .map(|sound| Ok(Arc::from(assets.load(sound.path())?)))
+            .collect::<std::io::Result<_>>()?;
+        let output = match OutputStream::try_default() {
+            Ok(output) => Some(output),
+            Err(e) => {
+                log::warn!("Couldn't open an audio device: {}", e);
+                None
+            }
+        };
+        Ok(Sounds {
+            assets,
+            data,
+            output,
+        })
+    }
+
+    pub fn play(&self, sound: Sound) {
+        if let Some((_, handle)) = &self.output {
+            let cursor = std::io::Cursor::new(self.data[sound as usize].clone());
+            match rodio::Decoder::new(cursor) {
+                Ok(source) => _ = handle.play_raw(source.convert_samples()),
+                Err(e) => log::error!("Couldn't decode {}: {}", sound.path(), e),
+            }
+        }
+    }
 
-pub fn play_sound(sound: Sound) {
-    let (_stream, stream_handle) = rodio::OutputStream::try_default().unwrap();
-    let cursor = std::io::Cursor::new(SOUND_DATA[sound as usize]);
-    let source = rodio::Decoder::new(cursor).unwrap();
-    _ = stream_handle.play_raw(source.convert_samples());
+    pub fn reload(&mut self) {
+        for path in self.assets.changed() {
+            for sound in Sound::ALL {
+                if path.as_path() == std::path::Path::new(sound.path()) {
+                    match self.assets.load(&path) {
+                        Ok(data) => {
+                            self.data[sound as usize] = Arc::from(data);
+                            log::info!("Reloaded {}", path.display());
+                        }
+                        Err(e) => log::error!("Couldn't reload {}: {}", path.display(), e),
+                    }
+                }
+            }
+        }
+    }
 }
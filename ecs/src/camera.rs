@@ -0,0 +1,152 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+extern crate graphics;
+
+pub struct Camera {
+    pub target: (f32, f32),
+    pub target_velocity: (f32, f32),
+    pub smoothing: f32,
+    pub dead_zone: (f32, f32),
+    pub look_ahead: f32,
+    pub look_ahead_smoothing: f32,
+    pub bounds: Option<(f32, f32, f32, f32)>,
+    pub zoom: f32,
+    pub trauma: f32,
+    pub trauma_decay: f32,
+    pub max_shake: f32,
+    pub max_roll: f32,
+    pub shake_frequency: f32,
+    pub position: (f32, f32),
+    look: (f32, f32),
+    time: f32,
+    started: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            target: (0.0, 0.0),
+            target_velocity: (0.0, 0.0),
+            smoothing: 8.0,
+            dead_zone: (12.0, 16.0),
+            look_ahead: 0.3,
+            look_ahead_smoothing: 3.0,
+            bounds: None,
+            zoom: 1.0,
+            trauma: 0.0,
+            trauma_decay: 1.5,
+            max_shake: 6.0,
+            max_roll: 0.05,
+            shake_frequency: 25.0,
+            position: (0.0, 0.0),
+            look: (0.0, 0.0),
+            time: 0.0,
+            started: false,
+        }
+    }
+}
+
+fn approach_exp(value: f32, target: f32, rate: f32, dt: f32) -> f32 {
+    if rate <= 0.0 {
+        target
+    } else {
+        value + (target - value) * (1.0 - (-rate * dt).exp())
+    }
+}
+
+fn clamp_axis(position: f32, half: f32, min: f32, max: f32) -> f32 {
+    if max - min <= 2.0 * half {
+        (min + max) / 2.0
+    } else {
+        position.clamp(min + half, max - half)
+    }
+}
+
+fn noise(t: f32, seed: f32) -> f32 {
+    ((t * 1.0 + seed).sin()
+        + (t * 2.3 + seed * 1.7).sin() * 0.5
+        + (t * 4.1 + seed * 2.9).sin() * 0.25)
+        / 1.75
+}
+
+impl Camera {
+    pub fn follow(&mut self, x: f32, y: f32, vel: (f32, f32)) {
+        self.target = (x, y);
+        self.target_velocity = vel;
+    }
+
+    pub fn clamp_to(&mut self, tiles: &graphics::Tiles) {
+        let tile = graphics::TILE_SIZE as f32;
+        self.bounds = tiles.bounds().map(|(x0, y0, x1, y1)| {
+            (
+                x0 as f32 * tile,
+                y0 as f32 * tile,
+                (x1 + 1) as f32 * tile,
+                (y1 + 1) as f32 * tile,
+            )
+        });
+    }
+
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    pub fn snap(&mut self) {
+        self.look = (0.0, 0.0);
+        self.position = self.target;
+        self.started = true;
+    }
+
+    pub fn update(&mut self, dt: f32, viewport: (u32, u32)) -> graphics::View {
+        if !self.started {
+            self.snap();
+        }
+        self.time += dt;
+
+        let look = (
+            self.target_velocity.0 * self.look_ahead,
+            self.target_velocity.1 * self.look_ahead,
+        );
+        self.look.0 = approach_exp(self.look.0, look.0, self.look_ahead_smoothing, dt);
+        self.look.1 = approach_exp(self.look.1, look.1, self.look_ahead_smoothing, dt);
+        let focus = (self.target.0 + self.look.0, self.target.1 + self.look.1);
+
+        let mut desired = self.position;
+        desired.0 = desired
+            .0
+            .clamp(focus.0 - self.dead_zone.0, focus.0 + self.dead_zone.0);
+        desired.1 = desired
+            .1
+            .clamp(focus.1 - self.dead_zone.1, focus.1 + self.dead_zone.1);
+        self.position.0 = approach_exp(self.position.0, desired.0, self.smoothing, dt);
+        self.position.1 = approach_exp(self.position.1, desired.1, self.smoothing, dt);
+
+        let mut view = graphics::View::new(self.position.0, self.position.1);
+        view.zoom = self.zoom;
+        if let Some((min_x, min_y, max_x, max_y)) = self.bounds {
+            let (half_w, half_h) = view.half_extents(viewport);
+            self.position.0 = clamp_axis(self.position.0, half_w, min_x, max_x);
+            self.position.1 = clamp_axis(self.position.1, half_h, min_y, max_y);
+        }
+
+        self.trauma = (self.trauma - self.trauma_decay * dt).max(0.0);
+        let shake = self.trauma * self.trauma;
+        let t = self.time * self.shake_frequency;
+        view.x = self.position.0 + self.max_shake * shake * noise(t, 0.0);
+        view.y = self.position.1 + self.max_shake * shake * noise(t, 10.0);
+        view.rotation = self.max_roll * shake * noise(t, 20.0);
+        view
+    }
+}
@@ -22,7 +22,7 @@ pub trait Component {
         Self: Sized;
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub struct AABB {
     pub x: f32,
     pub y: f32,
@@ -49,6 +49,23 @@ impl Component for Velocity {
     }
 }
 
+#[derive(Debug)]
+pub struct PhysicsBody {
+    pub gravity_scale: f32,
+    pub gravity_multiplier: f32,
+    pub drag: f32,
+    pub max_fall_speed: f32,
+    pub friction: Option<f32>,
+    pub restitution: Option<f32>,
+    pub impact: (f32, f32),
+}
+
+impl Component for PhysicsBody {
+    fn get_host_vec(components: &mut Components) -> &mut Vec<Option<PhysicsBody>> {
+        &mut components.bodies
+    }
+}
+
 #[derive(Debug)]
 pub struct Sprite {
     pub sprite: graphics::sprite::Sprite,
@@ -57,6 +74,14 @@ pub struct Sprite {
     pub height: f32,
     pub off_x: f32,
     pub off_y: f32,
+    pub layer: i32,
+    pub depth: f32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub rotation: f32,
+    pub pivot: (f32, f32),
+    pub tint: [f32; 4],
+    pub alpha: f32,
 }
 
 impl Component for Sprite {
@@ -66,12 +91,265 @@ impl Component for Sprite {
 }
 
 #[derive(Debug)]
-pub struct Player {
-    pub can_jump: f32,
-}
+pub struct Player;
 
 impl Component for Player {
     fn get_host_vec(components: &mut Components) -> &mut Vec<Option<Player>> {
         &mut components.players
     }
 }
+
+#[derive(Debug)]
+pub struct PlatformerController {
+    pub run_speed: f32,
+    pub ground_accel: f32,
+    pub ground_decel: f32,
+    pub air_accel: f32,
+    pub air_decel: f32,
+    pub jump_speed: f32,
+    pub jump_cut: f32,
+    pub coyote_time: f32,
+    pub jump_buffer_time: f32,
+    pub wall_slide_speed: f32,
+    pub wall_jump_speed: (f32, f32),
+    pub crouch_gravity_scale: f32,
+    pub climb_speed: f32,
+    pub grounded: bool,
+    pub climbing: bool,
+    pub coyote: f32,
+    pub wall_coyote: f32,
+    pub wall_dir: f32,
+    pub jump_buffer: f32,
+    pub jump_held: bool,
+    pub jumping: bool,
+    pub facing: f32,
+}
+
+impl Default for PlatformerController {
+    fn default() -> Self {
+        Self {
+            run_speed: 100.0,
+            ground_accel: 1000.0,
+            ground_decel: 1200.0,
+            air_accel: 600.0,
+            air_decel: 300.0,
+            jump_speed: 100.0,
+            jump_cut: 0.5,
+            coyote_time: 0.1,
+            jump_buffer_time: 0.1,
+            wall_slide_speed: 40.0,
+            wall_jump_speed: (100.0, 100.0),
+            crouch_gravity_scale: 2.0,
+            climb_speed: 60.0,
+            grounded: false,
+            climbing: false,
+            coyote: 0.0,
+            wall_coyote: 0.0,
+            wall_dir: 0.0,
+            jump_buffer: 0.0,
+            jump_held: false,
+            jumping: false,
+            facing: 1.0,
+        }
+    }
+}
+
+impl Component for PlatformerController {
+    fn get_host_vec(components: &mut Components) -> &mut Vec<Option<PlatformerController>> {
+        &mut components.controllers
+    }
+}
+
+#[derive(Debug)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+    pub invulnerability: f32,
+    pub cooldown: f32,
+}
+
+impl Component for Health {
+    fn get_host_vec(components: &mut Components) -> &mut Vec<Option<Health>> {
+        &mut components.healths
+    }
+}
+
+#[derive(Debug)]
+pub struct Animator {
+    pub clip: String,
+    pub frame: usize,
+    pub time: f32,
+    pub speed: f32,
+    pub reverse: bool,
+    pub finished: bool,
+    pub events: Vec<String>,
+    started: bool,
+}
+
+impl Animator {
+    pub fn new(clip: &str) -> Self {
+        Animator {
+            clip: String::from(clip),
+            frame: 0,
+            time: 0.0,
+            speed: 1.0,
+            reverse: false,
+            finished: false,
+            events: Vec::new(),
+            started: false,
+        }
+    }
+
+    pub fn play(&mut self, clip: &str) {
+        if self.clip != clip {
+            self.restart(clip);
+        }
+    }
+
+    pub fn restart(&mut self, clip: &str) {
+        self.clip.clear();
+        self.clip.push_str(clip);
+        self.frame = 0;
+        self.time = 0.0;
+        self.reverse = false;
+        self.finished = false;
+        self.started = false;
+    }
+
+    pub fn update(&mut self, sheet: &graphics::SpriteSheet, dt: f32) -> Option<usize> {
+        self.events.clear();
+        let clip = sheet.clip(&self.clip)?;
+        if !self.started {
+            self.started = true;
+            self.fire(clip);
+        }
+        self.time += dt * self.speed;
+        while !self.finished && self.time >= clip.durations[self.frame] {
+            self.time -= clip.durations[self.frame];
+            let last = clip.len() - 1;
+            match clip.mode {
+                graphics::PlayMode::Loop => {
+                    self.frame = if self.frame == last {
+                        0
+                    } else {
+                        self.frame + 1
+                    };
+                }
+                graphics::PlayMode::PingPong => {
+                    if self.frame == last {
+                        self.reverse = true;
+                    } else if self.frame == 0 {
+                        self.reverse = false;
+                    }
+                    if last > 0 {
+                        self.frame = if self.reverse {
+                            self.frame - 1
+                        } else {
+                            self.frame + 1
+                        };
+                    }
+                }
+                graphics::PlayMode::Once => {
+                    if self.frame == last {
+                        self.finished = true;
+                        self.time = 0.0;
+                        break;
+                    }
+                    self.frame += 1;
+                }
+            }
+            self.fire(clip);
+        }
+        Some(clip.first + self.frame)
+    }
+
+    fn fire(&mut self, clip: &graphics::Clip) {
+        self.events
+            .extend(clip.events_at(self.frame).map(String::from));
+    }
+}
+
+impl Component for Animator {
+    fn get_host_vec(components: &mut Components) -> &mut Vec<Option<Animator>> {
+        &mut components.animators
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum KinematicPath {
+    Waypoints {
+        points: Vec<(f32, f32)>,
+        speed: f32,
+        looped: bool,
+    },
+    Sine {
+        origin: (f32, f32),
+        amplitude: (f32, f32),
+        period: f32,
+        phase: f32,
+    },
+}
+
+impl KinematicPath {
+    pub fn position(&self, time: f32) -> (f32, f32) {
+        match self {
+            KinematicPath::Waypoints {
+                points,
+                speed,
+                looped,
+            } => {
+                if points.len() < 2 {
+                    return points.first().copied().unwrap_or((0.0, 0.0));
+                }
+                let mut segments: Vec<((f32, f32), (f32, f32))> =
+                    points.windows(2).map(|w| (w[0], w[1])).collect();
+                if *looped {
+                    segments.push((points[points.len() - 1], points[0]));
+                }
+                let length = |(a, b): &((f32, f32), (f32, f32))| {
+                    ((b.0 - a.0) * (b.0 - a.0) + (b.1 - a.1) * (b.1 - a.1)).sqrt()
+                };
+                let total: f32 = segments.iter().map(length).sum();
+                if total == 0.0 {
+                    return points[0];
+                }
+                let mut dist = (speed * time).rem_euclid(if *looped { total } else { 2.0 * total });
+                if dist > total {
+                    dist = 2.0 * total - dist;
+                }
+                for segment in segments.iter() {
+                    let seg_len = length(segment);
+                    if dist <= seg_len && seg_len > 0.0 {
+                        let t = dist / seg_len;
+                        let (a, b) = segment;
+                        return (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+                    }
+                    dist -= seg_len;
+                }
+                segments[segments.len() - 1].1
+            }
+            KinematicPath::Sine {
+                origin,
+                amplitude,
+                period,
+                phase,
+            } => {
+                let s = (std::f32::consts::TAU * time / period + phase).sin();
+                (origin.0 + amplitude.0 * s, origin.1 + amplitude.1 * s)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Kinematic {
+    pub path: KinematicPath,
+    pub time: f32,
+    pub vel: (f32, f32),
+}
+
+impl Component for Kinematic {
+    fn get_host_vec(components: &mut Components) -> &mut Vec<Option<Kinematic>> {
+        &mut components.kinematics
+    }
+}
@@ -22,30 +22,42 @@ extern crate graphics;
 #[derive(Clone, Copy, Deserialize, Serialize)]
 pub enum EntityDesc {
     Player(PlayerDesc),
+    Platform(PlatformDesc),
 }
 
 impl EntityDesc {
     pub fn get_sprite(&self) -> graphics::Sprite {
         match self {
             EntityDesc::Player(x) => x.get_sprite(),
+            EntityDesc::Platform(x) => x.get_sprite(),
         }
     }
 
-    pub fn construct(&self, world: &mut World) {
+    pub fn construct(&self, world: &mut World) -> Entity {
         match self {
             EntityDesc::Player(x) => x.construct(world),
+            EntityDesc::Platform(x) => x.construct(world),
+        }
+    }
+
+    pub fn streamed(&self) -> bool {
+        match self {
+            EntityDesc::Player(_) => false,
+            EntityDesc::Platform(_) => true,
         }
     }
 
     pub fn get_pos(&self) -> (f32, f32) {
         match self {
             EntityDesc::Player(x) => x.get_pos(),
+            EntityDesc::Platform(x) => x.get_pos(),
         }
     }
 
     pub fn adjust_pos(&mut self, dx: f32, dy: f32) {
         match self {
             EntityDesc::Player(x) => x.adjust_pos(dx, dy),
+            EntityDesc::Platform(x) => x.adjust_pos(dx, dy),
         }
     }
 }
@@ -61,7 +73,7 @@ impl PlayerDesc {
         graphics::sprite::Sprite::TestSprite1
     }
 
-    fn construct(&self, world: &mut World) {
+    fn construct(&self, world: &mut World) -> Entity {
         let entity = world.add();
         world.insert(
             entity,
@@ -74,6 +86,18 @@ impl PlayerDesc {
             },
         );
         world.insert(entity, Velocity { x: 0.0, y: 0.0 });
+        world.insert(
+            entity,
+            PhysicsBody {
+                gravity_scale: 1.0,
+                gravity_multiplier: 1.0,
+                drag: 0.0,
+                max_fall_speed: 300.0,
+                friction: None,
+                restitution: None,
+                impact: (0.0, 0.0),
+            },
+        );
         world.insert(
             entity,
             Sprite {
@@ -83,9 +107,102 @@ impl PlayerDesc {
                 height: 1.0,
                 off_x: 0.0,
                 off_y: 0.5,
+                layer: graphics::SPRITE_LAYER,
+                depth: 0.0,
+                flip_x: false,
+                flip_y: false,
+                rotation: 0.0,
+                pivot: (0.0, 0.0),
+                tint: [1.0; 4],
+                alpha: 1.0,
+            },
+        );
+        world.insert(entity, Animator::new("idle"));
+        world.insert(entity, PlatformerController::default());
+        world.insert(
+            entity,
+            Health {
+                current: 100.0,
+                max: 100.0,
+                invulnerability: 1.0,
+                cooldown: 0.0,
+            },
+        );
+        world.insert(entity, Player);
+        entity
+    }
+
+    pub fn get_pos(&self) -> (f32, f32) {
+        (self.x, self.y)
+    }
+
+    fn adjust_pos(&mut self, dx: f32, dy: f32) {
+        self.x += dx;
+        self.y += dy;
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct PlatformDesc {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+    pub dx: f32,
+    pub dy: f32,
+    pub period: f32,
+}
+
+impl PlatformDesc {
+    fn get_sprite(&self) -> graphics::Sprite {
+        graphics::sprite::Sprite::TestSprite2
+    }
+
+    fn construct(&self, world: &mut World) -> Entity {
+        let entity = world.add();
+        world.insert(
+            entity,
+            AABB {
+                x: self.x,
+                y: self.y,
+                w: self.w,
+                h: self.h,
+                last: 0,
+            },
+        );
+        world.insert(
+            entity,
+            Kinematic {
+                path: KinematicPath::Sine {
+                    origin: (self.x, self.y),
+                    amplitude: (self.dx, self.dy),
+                    period: self.period,
+                    phase: 0.0,
+                },
+                time: 0.0,
+                vel: (0.0, 0.0),
+            },
+        );
+        world.insert(
+            entity,
+            Sprite {
+                sprite: self.get_sprite(),
+                frame: 0,
+                width: self.w / graphics::TILE_SIZE as f32,
+                height: self.h / graphics::TILE_SIZE as f32,
+                off_x: 0.0,
+                off_y: 0.0,
+                layer: graphics::SPRITE_LAYER,
+                depth: 1.0,
+                flip_x: false,
+                flip_y: false,
+                rotation: 0.0,
+                pivot: (0.0, 0.0),
+                tint: [1.0; 4],
+                alpha: 1.0,
             },
         );
-        world.insert(entity, Player { can_jump: 0.0 });
+        entity
     }
 
     pub fn get_pos(&self) -> (f32, f32) {
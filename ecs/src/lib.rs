@@ -12,16 +12,22 @@
  * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod camera;
 pub mod components;
 pub mod entities;
+pub mod raycast;
 pub mod resources;
+pub mod streaming;
 pub mod systems;
 pub mod util;
 pub mod world;
 
+pub use camera::*;
 pub use components::*;
 pub use entities::*;
+pub use raycast::*;
 pub use resources::*;
+pub use streaming::*;
 pub use systems::*;
 pub use util::*;
 pub use world::*;
@@ -0,0 +1,300 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::components::*;
+use super::util::*;
+use super::world::*;
+
+extern crate graphics;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    pub x: f32,
+    pub y: f32,
+    pub distance: f32,
+    pub normal: (f32, f32),
+}
+
+fn normalize(dir: (f32, f32)) -> Option<(f32, f32)> {
+    let magnitude = (dir.0 * dir.0 + dir.1 * dir.1).sqrt();
+    if magnitude == 0.0 || !magnitude.is_finite() {
+        None
+    } else {
+        Some((dir.0 / magnitude, dir.1 / magnitude))
+    }
+}
+
+fn solid_tile(tiles: &graphics::Tiles, x: i64, y: i64) -> Option<graphics::Tile> {
+    match tiles.collision_at(x, y) {
+        Some(graphics::Tile::NO_TILE) => None,
+        tile => tile,
+    }
+}
+
+// The tile range covered by loaded chunks of the collision layers.
+fn collision_bounds(tiles: &graphics::Tiles) -> Option<(i64, i64, i64, i64)> {
+    let size = graphics::CHUNK_SIZE as i64;
+    tiles
+        .layers
+        .iter()
+        .filter(|layer| layer.collision)
+        .flat_map(|layer| layer.chunks().map(|(coords, _)| *coords))
+        .map(|(x, y)| (x * size, y * size, x * size + size - 1, y * size + size - 1))
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+}
+
+pub fn raycast_aabb(
+    origin: (f32, f32),
+    dir: (f32, f32),
+    max_dist: f32,
+    aabb: &AABB,
+) -> Option<RayHit> {
+    let dir = normalize(dir)?;
+    let min = (aabb.x - aabb.w / 2.0, aabb.y - aabb.h / 2.0);
+    let max = (aabb.x + aabb.w / 2.0, aabb.y + aabb.h / 2.0);
+
+    let mut t_near = f32::NEG_INFINITY;
+    let mut t_far = f32::INFINITY;
+    let mut normal = (0.0, 0.0);
+    for (o, d, lo, hi, axis_normal) in [
+        (origin.0, dir.0, min.0, max.0, (-dir.0.signum(), 0.0)),
+        (origin.1, dir.1, min.1, max.1, (0.0, -dir.1.signum())),
+    ] {
+        if d == 0.0 {
+            if o < lo || o > hi {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (lo - o) / d;
+        let t2 = (hi - o) / d;
+        let (t1, t2) = if t1 < t2 { (t1, t2) } else { (t2, t1) };
+        if t1 > t_near {
+            t_near = t1;
+            normal = axis_normal;
+        }
+        t_far = t_far.min(t2);
+    }
+
+    if t_near > t_far || t_far < 0.0 || t_near > max_dist {
+        return None;
+    }
+    if t_near < 0.0 {
+        return Some(RayHit {
+            x: origin.0,
+            y: origin.1,
+            distance: 0.0,
+            normal: (0.0, 0.0),
+        });
+    }
+    Some(RayHit {
+        x: origin.0 + dir.0 * t_near,
+        y: origin.1 + dir.1 * t_near,
+        distance: t_near,
+        normal,
+    })
+}
+
+pub fn raycast_tiles(
+    origin: (f32, f32),
+    dir: (f32, f32),
+    max_dist: f32,
+    tiles: &graphics::Tiles,
+) -> Option<(graphics::Tile, i64, i64, RayHit)> {
+    let dir = normalize(dir)?;
+    let bounds = collision_bounds(tiles)?;
+    let tile_size = graphics::TILE_SIZE as f32;
+    let mut cell = (
+        (origin.0 / tile_size).floor() as i64,
+        (origin.1 / tile_size).floor() as i64,
+    );
+    let step = (dir.0.signum() as i64, dir.1.signum() as i64);
+    let t_delta = (
+        if dir.0 != 0.0 {
+            tile_size / dir.0.abs()
+        } else {
+            f32::INFINITY
+        },
+        if dir.1 != 0.0 {
+            tile_size / dir.1.abs()
+        } else {
+            f32::INFINITY
+        },
+    );
+    let mut t_max = (
+        if dir.0 > 0.0 {
+            ((cell.0 + 1) as f32 * tile_size - origin.0) / dir.0
+        } else if dir.0 < 0.0 {
+            (cell.0 as f32 * tile_size - origin.0) / dir.0
+        } else {
+            f32::INFINITY
+        },
+        if dir.1 > 0.0 {
+            ((cell.1 + 1) as f32 * tile_size - origin.1) / dir.1
+        } else if dir.1 < 0.0 {
+            (cell.1 as f32 * tile_size - origin.1) / dir.1
+        } else {
+            f32::INFINITY
+        },
+    );
+
+    let mut t = 0.0;
+    let mut normal = (0.0, 0.0);
+    while t <= max_dist {
+        if let Some(tile) = solid_tile(tiles, cell.0, cell.1) {
+            return Some((
+                tile,
+                cell.0,
+                cell.1,
+                RayHit {
+                    x: origin.0 + dir.0 * t,
+                    y: origin.1 + dir.1 * t,
+                    distance: t,
+                    normal,
+                },
+            ));
+        }
+        // Past the loaded tiles and moving away, nothing more can be hit.
+        if cell.0 < bounds.0 && step.0 <= 0
+            || cell.1 < bounds.1 && step.1 <= 0
+            || cell.0 > bounds.2 && step.0 >= 0
+            || cell.1 > bounds.3 && step.1 >= 0
+        {
+            return None;
+        }
+        if t_max.0 < t_max.1 {
+            cell.0 += step.0;
+            t = t_max.0;
+            t_max.0 += t_delta.0;
+            normal = (-step.0 as f32, 0.0);
+        } else {
+            cell.1 += step.1;
+            t = t_max.1;
+            t_max.1 += t_delta.1;
+            normal = (0.0, -step.1 as f32);
+        }
+    }
+    None
+}
+
+pub fn raycast_entities(
+    origin: (f32, f32),
+    dir: (f32, f32),
+    max_dist: f32,
+    components: &Components,
+    ignore: Option<Entity>,
+) -> Option<(Entity, RayHit)> {
+    let mut closest: Option<(Entity, RayHit)> = None;
+    for (index, aabb) in components.aabbs.iter().enumerate() {
+        let entity = Entity {
+            index,
+            generation: components.generations[index],
+        };
+        if ignore == Some(entity) {
+            continue;
+        }
+        if let Some(aabb) = aabb {
+            if let Some(hit) = raycast_aabb(origin, dir, max_dist, aabb) {
+                if closest.is_none_or(|(_, c)| hit.distance < c.distance) {
+                    closest = Some((entity, hit));
+                }
+            }
+        }
+    }
+    closest
+}
+
+pub fn sweep_aabb(aabb: &AABB, delta: (f32, f32), other: &AABB) -> Option<RayHit> {
+    let distance = (delta.0 * delta.0 + delta.1 * delta.1).sqrt();
+    let expanded = AABB {
+        x: other.x,
+        y: other.y,
+        w: other.w + aabb.w,
+        h: other.h + aabb.h,
+        last: 0,
+    };
+    if distance == 0.0 {
+        return if collides(aabb, other) {
+            Some(RayHit {
+                x: aabb.x,
+                y: aabb.y,
+                distance: 0.0,
+                normal: (0.0, 0.0),
+            })
+        } else {
+            None
+        };
+    }
+    raycast_aabb((aabb.x, aabb.y), delta, distance, &expanded)
+}
+
+pub fn sweep_aabb_tiles(
+    aabb: &AABB,
+    delta: (f32, f32),
+    tiles: &graphics::Tiles,
+) -> Option<(graphics::Tile, i64, i64, RayHit)> {
+    let swept = AABB {
+        x: aabb.x + delta.0 / 2.0,
+        y: aabb.y + delta.1 / 2.0,
+        w: aabb.w + delta.0.abs(),
+        h: aabb.h + delta.1.abs(),
+        last: 0,
+    };
+    let tile_size = graphics::TILE_SIZE as f32;
+    let mut closest: Option<(graphics::Tile, i64, i64, RayHit)> = None;
+    for (tile, tx, ty) in get_all_tiles_in_aabb(&swept, tiles) {
+        if tile == graphics::Tile::NO_TILE {
+            continue;
+        }
+        let tile_aabb = AABB {
+            x: (tx as f32 + 0.5) * tile_size,
+            y: (ty as f32 + 0.5) * tile_size,
+            w: tile_size,
+            h: tile_size,
+            last: 0,
+        };
+        if let Some(hit) = sweep_aabb(aabb, delta, &tile_aabb) {
+            if closest.is_none_or(|(_, _, _, c)| hit.distance < c.distance) {
+                closest = Some((tile, tx, ty, hit));
+            }
+        }
+    }
+    closest
+}
+
+pub fn sweep_aabb_entities(
+    aabb: &AABB,
+    delta: (f32, f32),
+    components: &Components,
+    ignore: Option<Entity>,
+) -> Option<(Entity, RayHit)> {
+    let mut closest: Option<(Entity, RayHit)> = None;
+    for (index, other) in components.aabbs.iter().enumerate() {
+        let entity = Entity {
+            index,
+            generation: components.generations[index],
+        };
+        if ignore == Some(entity) {
+            continue;
+        }
+        if let Some(other) = other {
+            if let Some(hit) = sweep_aabb(aabb, delta, other) {
+                if closest.is_none_or(|(_, c)| hit.distance < c.distance) {
+                    closest = Some((entity, hit));
+                }
+            }
+        }
+    }
+    closest
+}
@@ -14,6 +14,8 @@
 
 use std::time::Instant;
 
+use super::components::*;
+
 extern crate graphics;
 
 pub struct Timer {
@@ -21,6 +23,8 @@ pub struct Timer {
     stopwatch: u32,
     dt: f32,
     second_border: bool,
+    frames: u32,
+    fps: u32,
 }
 
 impl Timer {
@@ -30,6 +34,8 @@ impl Timer {
             stopwatch: 0,
             dt: 0.0,
             second_border: false,
+            frames: 0,
+            fps: 0,
         }
     }
 
@@ -38,12 +44,25 @@ impl Timer {
         let before = self.stopwatch;
         self.stopwatch = self.micros();
         self.second_border = (self.stopwatch % 1000000) < (before % 1000000);
+        self.frames += 1;
+        if self.second_border {
+            self.fps = self.frames;
+            self.frames = 0;
+        }
     }
 
     pub fn dt(&self) -> f32 {
         self.dt
     }
 
+    pub fn fps(&self) -> u32 {
+        self.fps
+    }
+
+    pub fn seconds(&self) -> f32 {
+        self.stopwatch as f32 / 1000000.0
+    }
+
     pub fn micros(&self) -> u32 {
         self.start.elapsed().as_micros() as u32
     }
@@ -64,17 +83,34 @@ impl SpriteBatchRes {
         SpriteBatchRes { sprite_batch }
     }
 
-    pub fn insert(
-        &mut self,
-        sprite: graphics::Sprite,
-        frame: usize,
-        x: f32,
-        y: f32,
-        w: f32,
-        h: f32,
-    ) {
+    pub fn insert(&mut self, sprite: graphics::Sprite, instance: graphics::SpriteInstance) {
+        unsafe {
+            (*self.sprite_batch)[sprite as usize].push(instance);
+        }
+    }
+}
+
+pub struct TextBatchRes {
+    pub text_batch: *mut graphics::TextBatch,
+}
+
+impl TextBatchRes {
+    pub fn new(text_batch: *mut graphics::TextBatch) -> Self {
+        TextBatchRes { text_batch }
+    }
+
+    pub fn insert(&mut self, text: graphics::Text) {
         unsafe {
-            (*self.sprite_batch)[sprite as usize].push((frame, x, y, w, h));
+            (*self.text_batch).push(text);
         }
     }
 }
+
+#[derive(Clone, Copy, Debug)]
+pub struct Solid {
+    pub aabb: AABB,
+    pub vel: (f32, f32),
+}
+
+#[derive(Default)]
+pub struct Solids(pub Vec<Solid>);
@@ -0,0 +1,365 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+use super::components::*;
+use super::entities::*;
+use super::world::*;
+
+extern crate graphics;
+
+const CHUNK_WORLD_SIZE: f32 = (graphics::CHUNK_SIZE * graphics::TILE_SIZE) as f32;
+
+#[derive(Clone, Copy, Debug)]
+pub struct StreamingConfig {
+    pub radius: i64,
+    pub unload_margin: i64,
+    pub max_loads_per_frame: usize,
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        StreamingConfig {
+            radius: 2,
+            unload_margin: 1,
+            max_loads_per_frame: 4,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct StreamedManifest {
+    pub tiles: graphics::Tiles,
+    pub chunks: Vec<(i64, i64)>,
+    pub entities: Vec<EntityDesc>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct StreamedChunk {
+    pub layers: Vec<(String, graphics::Chunk)>,
+    pub entities: Vec<EntityDesc>,
+}
+
+fn invalid(e: bincode::Error) -> Error {
+    Error::new(ErrorKind::InvalidData, e)
+}
+
+fn manifest_path(dir: &Path) -> PathBuf {
+    dir.join("world.bin")
+}
+
+fn chunk_path(dir: &Path, coords: (i64, i64)) -> PathBuf {
+    dir.join("chunks")
+        .join(format!("{}_{}.bin", coords.0, coords.1))
+}
+
+fn tile_chunk(x: i64, y: i64) -> (i64, i64) {
+    (
+        x.div_euclid(graphics::CHUNK_SIZE as i64),
+        y.div_euclid(graphics::CHUNK_SIZE as i64),
+    )
+}
+
+pub fn chunk_at(x: f32, y: f32) -> (i64, i64) {
+    (
+        (x / CHUNK_WORLD_SIZE).floor() as i64,
+        (y / CHUNK_WORLD_SIZE).floor() as i64,
+    )
+}
+
+fn read_chunk(dir: &Path, coords: (i64, i64)) -> std::io::Result<StreamedChunk> {
+    bincode::deserialize(&std::fs::read(chunk_path(dir, coords))?).map_err(invalid)
+}
+
+pub fn save_streamed<P: AsRef<Path>>(
+    dir: P,
+    tiles: &graphics::Tiles,
+    entities: &[EntityDesc],
+) -> std::io::Result<()> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir.join("chunks"))?;
+
+    let mut manifest = StreamedManifest {
+        tiles: Default::default(),
+        chunks: Vec::new(),
+        entities: Vec::new(),
+    };
+    manifest.tiles.layers.clear();
+    let mut chunks: HashMap<(i64, i64), StreamedChunk> = HashMap::new();
+    for layer in tiles.layers.iter() {
+        manifest.tiles.layers.push(graphics::TileLayer::new(
+            &layer.name,
+            layer.order,
+            layer.parallax,
+            layer.collision,
+        ));
+        for (coords, chunk) in layer.chunks() {
+            chunks
+                .entry(*coords)
+                .or_default()
+                .layers
+                .push((layer.name.clone(), chunk.tiles));
+        }
+    }
+    for entity in entities.iter() {
+        if entity.streamed() {
+            let (x, y) = entity.get_pos();
+            chunks
+                .entry(chunk_at(x, y))
+                .or_default()
+                .entities
+                .push(*entity);
+        } else {
+            manifest.entities.push(*entity);
+        }
+    }
+
+    for (coords, chunk) in chunks.iter() {
+        let mut file = File::create(chunk_path(dir, *coords))?;
+        file.write_all(&bincode::serialize(chunk).map_err(invalid)?)?;
+        manifest.chunks.push(*coords);
+    }
+    let mut file = File::create(manifest_path(dir))?;
+    file.write_all(&bincode::serialize(&manifest).map_err(invalid)?)?;
+    Ok(())
+}
+
+fn distance(a: (i64, i64), b: (i64, i64)) -> i64 {
+    (a.0 - b.0).abs().max((a.1 - b.1).abs())
+}
+
+type StreamedId = ((i64, i64), usize);
+
+struct StreamedEntity {
+    id: StreamedId,
+    desc: EntityDesc,
+    entity: Entity,
+}
+
+pub struct Streamer {
+    config: StreamingConfig,
+    chunks: HashSet<(i64, i64)>,
+    resident: HashSet<(i64, i64)>,
+    entities: Vec<StreamedEntity>,
+    parked: HashMap<StreamedId, EntityDesc>,
+    edits: HashMap<(i64, i64), Vec<graphics::TileChanged>>,
+    pending: HashSet<(i64, i64)>,
+    requests: Sender<(i64, i64)>,
+    loaded: Receiver<((i64, i64), std::io::Result<StreamedChunk>)>,
+}
+
+impl Streamer {
+    pub fn open<P: AsRef<Path>>(
+        world: &mut World,
+        dir: P,
+        config: StreamingConfig,
+    ) -> std::io::Result<()> {
+        let dir = dir.as_ref().to_path_buf();
+        let manifest: StreamedManifest =
+            bincode::deserialize(&std::fs::read(manifest_path(&dir))?).map_err(invalid)?;
+
+        let (requests, request_rx) = mpsc::channel::<(i64, i64)>();
+        let (loaded_tx, loaded) = mpsc::channel();
+        let thread_dir = dir.clone();
+        thread::spawn(move || {
+            for coords in request_rx {
+                if loaded_tx
+                    .send((coords, read_chunk(&thread_dir, coords)))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let mut streamer = Streamer {
+            config,
+            chunks: manifest.chunks.into_iter().collect(),
+            resident: HashSet::new(),
+            entities: Vec::new(),
+            parked: HashMap::new(),
+            edits: HashMap::new(),
+            pending: HashSet::new(),
+            requests,
+            loaded,
+        };
+
+        world.resources.tiles = manifest.tiles;
+        for entity in manifest.entities.iter() {
+            entity.construct(world);
+            if let EntityDesc::Player(player) = entity {
+                let camera = &mut world.resources.camera;
+                camera.follow(player.x, player.y, (0.0, 0.0));
+                camera.snap();
+            }
+        }
+
+        let (x, y) = world.resources.camera.position;
+        let center = chunk_at(x, y);
+        for coords in streamer.wanted(center) {
+            let chunk = read_chunk(&dir, coords)?;
+            streamer.insert(world, coords, chunk);
+        }
+        world.streamer = Some(streamer);
+        Ok(())
+    }
+
+    fn wanted(&self, center: (i64, i64)) -> Vec<(i64, i64)> {
+        let radius = self.config.radius;
+        let mut wanted = Vec::new();
+        for x in center.0 - radius..=center.0 + radius {
+            for y in center.1 - radius..=center.1 + radius {
+                if self.chunks.contains(&(x, y)) && !self.resident.contains(&(x, y)) {
+                    wanted.push((x, y));
+                }
+            }
+        }
+        wanted
+    }
+
+    fn insert(&mut self, world: &mut World, coords: (i64, i64), chunk: StreamedChunk) {
+        for (name, tiles) in chunk.layers {
+            if let Some(layer) = world.resources.tiles.layer_mut(&name) {
+                layer.insert_chunk(coords, tiles);
+            }
+        }
+        for edit in self.edits.get(&coords).into_iter().flatten() {
+            if let Some(layer) = world.resources.tiles.layer_mut(&edit.layer) {
+                layer.set(edit.x, edit.y, edit.new);
+            }
+        }
+        for (index, desc) in chunk.entities.iter().enumerate() {
+            if !self.is_spawned((coords, index)) {
+                self.parked.remove(&(coords, index));
+                self.spawn(world, (coords, index), *desc);
+            }
+        }
+        self.resident.insert(coords);
+    }
+
+    fn evict(&mut self, world: &mut World, coords: (i64, i64)) {
+        for layer in world.resources.tiles.layers.iter_mut() {
+            layer.remove_chunk(coords);
+        }
+        self.resident.remove(&coords);
+    }
+
+    fn is_spawned(&self, id: StreamedId) -> bool {
+        self.entities.iter().any(|streamed| streamed.id == id)
+    }
+
+    fn spawn(&mut self, world: &mut World, id: StreamedId, desc: EntityDesc) {
+        let entity = desc.construct(world);
+        self.entities.push(StreamedEntity { id, desc, entity });
+    }
+
+    fn park_far_entities(&mut self, world: &mut World, center: (i64, i64), limit: i64) {
+        for streamed in std::mem::take(&mut self.entities) {
+            let (x, y) = world
+                .get::<AABB>(streamed.entity)
+                .map_or(streamed.desc.get_pos(), |aabb| (aabb.x, aabb.y));
+            if distance(chunk_at(x, y), center) > limit {
+                world.remove(streamed.entity);
+                self.parked.insert(streamed.id, streamed.desc);
+            } else {
+                self.entities.push(streamed);
+            }
+        }
+    }
+
+    fn unpark_near_entities(&mut self, world: &mut World, center: (i64, i64)) {
+        let near: Vec<StreamedId> = self
+            .parked
+            .keys()
+            .filter(|(coords, _)| {
+                self.resident.contains(coords) && distance(*coords, center) <= self.config.radius
+            })
+            .copied()
+            .collect();
+        for id in near {
+            let desc = self.parked.remove(&id).unwrap();
+            self.spawn(world, id, desc);
+        }
+    }
+
+    fn record_edits(&mut self, tiles: &graphics::Tiles) {
+        for change in tiles.changes() {
+            let edits = self
+                .edits
+                .entry(tile_chunk(change.x, change.y))
+                .or_default();
+            edits.retain(|edit| {
+                (edit.layer.as_str(), edit.x, edit.y) != (change.layer.as_str(), change.x, change.y)
+            });
+            edits.push(change.clone());
+        }
+    }
+
+    pub fn update(&mut self, world: &mut World) {
+        self.record_edits(&world.resources.tiles);
+        self.entities
+            .retain(|streamed| world.is_alive(streamed.entity));
+        let center = chunk_at(
+            world.resources.camera.position.0,
+            world.resources.camera.position.1,
+        );
+
+        let limit = self.config.radius + self.config.unload_margin;
+        self.park_far_entities(world, center, limit);
+
+        for coords in self.wanted(center) {
+            if self.pending.insert(coords) {
+                self.requests.send(coords).ok();
+            }
+        }
+
+        for _ in 0..self.config.max_loads_per_frame {
+            let (coords, chunk) = match self.loaded.try_recv() {
+                Ok(x) => x,
+                Err(_) => break,
+            };
+            self.pending.remove(&coords);
+            match chunk {
+                Ok(chunk) => self.insert(world, coords, chunk),
+                Err(e) => log::error!("Couldn't load chunk {:?}: {}", coords, e),
+            }
+        }
+
+        self.unpark_near_entities(world, center);
+
+        let far: Vec<(i64, i64)> = self
+            .resident
+            .iter()
+            .filter(|coords| distance(**coords, center) > limit)
+            .copied()
+            .collect();
+        for coords in far {
+            self.evict(world, coords);
+        }
+    }
+
+    pub fn resident(&self) -> impl Iterator<Item = &(i64, i64)> {
+        self.resident.iter()
+    }
+}
@@ -12,6 +12,7 @@
  * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
  */
 
+use super::camera::*;
 use super::components::*;
 use super::resources::*;
 use super::util::*;
@@ -26,6 +27,10 @@ pub trait System {
         entity: Entity,
         resources: &mut Resources,
     ) -> Option<()>;
+
+    fn per_entity(&self) -> bool {
+        true
+    }
 }
 
 macro_rules! system_impl {
@@ -54,31 +59,140 @@ macro_rules! system_impl {
     };
     ($(($y:ident, $z: ty)),*) => {
         #[allow(unused_parens, non_snake_case)]
-        impl System for fn(($(&mut $z),*)) {
+        impl System for fn($(&mut $z),*) {
             fn run(&self, _components: &mut Components, _entity: Entity, resources: &mut Resources) -> Option<()> {
                 self($(&mut resources.$y),*);
                 Some(())
             }
+
+            fn per_entity(&self) -> bool {
+                false
+            }
         }
     };
 }
 
-system_impl!((timer, Timer));
-pub fn print_fps(timer: &mut Timer) {
-    if timer.second_border() {
-        println!("FPS: {}", 1.0 / timer.dt());
+system_impl!((timer, Timer), (text_batch_res, TextBatchRes));
+pub fn show_fps(timer: &mut Timer, text_batch: &mut TextBatchRes) {
+    text_batch.insert(graphics::Text::screen(
+        &format!("FPS: {}", timer.fps()),
+        2.0,
+        2.0,
+    ));
+}
+
+system_impl!(A, B, (timer, Timer), (solids, Solids));
+pub fn kinematic_system(
+    timer: &mut Timer,
+    solids: &mut Solids,
+    aabb: &mut AABB,
+    kinematic: &mut Kinematic,
+) {
+    kinematic.time += timer.dt();
+    let (x, y) = kinematic.path.position(kinematic.time);
+    kinematic.vel = if timer.dt() > 0.0 {
+        ((x - aabb.x) / timer.dt(), (y - aabb.y) / timer.dt())
+    } else {
+        (0.0, 0.0)
+    };
+    aabb.x = x;
+    aabb.y = y;
+    solids.0.push(Solid {
+        aabb: *aabb,
+        vel: kinematic.vel,
+    });
+}
+
+const MIN_BOUNCE_SPEED: f32 = 10.0;
+
+system_impl!(
+    A,
+    B,
+    C,
+    (timer, Timer),
+    (gravity, f32),
+    (tiles, graphics::Tiles)
+);
+pub fn physics_system(
+    timer: &mut Timer,
+    gravity: &mut f32,
+    tiles: &mut graphics::Tiles,
+    aabb: &mut AABB,
+    vel: &mut Velocity,
+    body: &mut PhysicsBody,
+) {
+    let contact = tile_contact(aabb, tiles);
+    let gravity_scale = body.gravity_scale * body.gravity_multiplier;
+    if let Some(restitution) = body.restitution {
+        let bounce = (-body.impact.0 * restitution, -body.impact.1 * restitution);
+        if (aabb.last & Correction::Left as u8 != 0 && body.impact.0 < 0.0
+            || aabb.last & Correction::Right as u8 != 0 && body.impact.0 > 0.0)
+            && bounce.0.abs() >= MIN_BOUNCE_SPEED
+        {
+            vel.x = bounce.0;
+        }
+        if (aabb.last & Correction::Up as u8 != 0 && body.impact.1 < 0.0
+            || aabb.last & Correction::Down as u8 != 0 && body.impact.1 > 0.0)
+            && bounce.1.abs() >= MIN_BOUNCE_SPEED
+        {
+            vel.y = bounce.1;
+        }
+    }
+    if let Some(friction) = body.friction.map(|f| f * contact.friction.unwrap_or(1.0)) {
+        if aabb.last & Correction::Up as u8 != 0 {
+            let decel = friction * *gravity * gravity_scale.abs() * timer.dt();
+            vel.x = if vel.x.abs() <= decel {
+                0.0
+            } else {
+                vel.x - decel * vel.x.signum()
+            };
+        }
     }
+    vel.y -= *gravity * gravity_scale * timer.dt();
+    let mut drag = body.drag;
+    if let Some(liquid) = contact.liquid {
+        vel.y += *gravity * gravity_scale.abs() * liquid.buoyancy * timer.dt();
+        drag += liquid.drag;
+    }
+    let damping = (1.0 - drag * timer.dt()).max(0.0);
+    vel.x *= damping;
+    vel.y *= damping;
+    vel.y = vel.y.max(-body.max_fall_speed);
+    body.impact = (vel.x, vel.y);
 }
 
-system_impl!(A, B, (timer, Timer), (tiles, graphics::Tiles));
+const RIDE_EPSILON: f32 = 0.5;
+
+fn rides(aabb: &AABB, solid: &Solid, dt: f32) -> bool {
+    let top = solid.aabb.y - solid.vel.1 * dt + solid.aabb.h / 2.0;
+    let bottom = aabb.y - aabb.h / 2.0;
+    aabb.last & Correction::Up as u8 != 0
+        && (bottom - top).abs() <= RIDE_EPSILON
+        && 2.0 * (aabb.x - (solid.aabb.x - solid.vel.0 * dt)).abs() < aabb.w + solid.aabb.w
+}
+
+system_impl!(
+    A,
+    B,
+    (timer, Timer),
+    (tiles, graphics::Tiles),
+    (solids, Solids)
+);
 pub fn update_aabb(
     timer: &mut Timer,
     tiles: &mut graphics::Tiles,
+    solids: &mut Solids,
     aabb: &mut AABB,
     vel: &mut Velocity,
 ) {
-    aabb.x += vel.x * timer.dt();
-    aabb.y += vel.y * timer.dt();
+    let carry = solids
+        .0
+        .iter()
+        .find(|solid| rides(aabb, solid, timer.dt()))
+        .map_or((0.0, 0.0), |solid| solid.vel);
+    let motion = (vel.x + carry.0, vel.y + carry.1);
+    aabb.x += motion.0 * timer.dt();
+    aabb.y += motion.1 * timer.dt();
     aabb.last = 0;
     let tiles_to_check = get_all_tiles_in_aabb(aabb, tiles);
 
@@ -87,14 +201,14 @@ pub fn update_aabb(
     for (tile_id, ux, uy) in tiles_to_check {
         match run_info {
             None => {
-                if tile_id != graphics::Tile::NoTile {
+                if tile_id != graphics::Tile::NO_TILE {
                     run_info = Some((ux, uy, ux, uy));
                 } else {
                     run_info = None;
                 }
             }
             Some((sx, sy, ex, ey)) => {
-                if tile_id != graphics::Tile::NoTile && uy == sy {
+                if tile_id != graphics::Tile::NO_TILE && uy == sy {
                     run_info = Some((sx, sy, ux, uy));
                 } else {
                     correct_collision(
@@ -110,10 +224,10 @@ pub fn update_aabb(
                             h: graphics::TILE_SIZE as f32,
                             last: 0,
                         },
-                        (vel.x, vel.y),
+                        motion,
                         timer.dt(),
                     );
-                    if tile_id != graphics::Tile::NoTile {
+                    if tile_id != graphics::Tile::NO_TILE {
                         run_info = Some((ux, uy, ux, uy));
                     } else {
                         run_info = None;
@@ -122,6 +236,14 @@ pub fn update_aabb(
             }
         }
     }
+    for solid in solids.0.iter_mut() {
+        correct_collision(
+            aabb,
+            &mut solid.aabb,
+            (motion.0 - solid.vel.0, motion.1 - solid.vel.1),
+            timer.dt(),
+        );
+    }
     if aabb.last & Correction::Left as u8 != 0 && vel.x < 0.0 {
         vel.x = 0.0;
     }
@@ -140,14 +262,32 @@ system_impl!(A, B, (sprite_batch_res, SpriteBatchRes));
 pub fn render_sprite(sprite_batch: &mut SpriteBatchRes, aabb: &mut AABB, sprite: &mut Sprite) {
     sprite_batch.insert(
         sprite.sprite,
-        sprite.frame,
-        aabb.x + sprite.off_x,
-        aabb.y + sprite.off_y,
-        sprite.width,
-        sprite.height,
+        graphics::SpriteInstance {
+            frame: sprite.frame,
+            x: aabb.x + sprite.off_x,
+            y: aabb.y + sprite.off_y,
+            w: sprite.width,
+            h: sprite.height,
+            layer: sprite.layer,
+            depth: sprite.depth,
+            flip_x: sprite.flip_x,
+            flip_y: sprite.flip_y,
+            rotation: sprite.rotation,
+            pivot: sprite.pivot,
+            tint: sprite.tint,
+            alpha: sprite.alpha,
+        },
     );
 }
 
+fn approach(value: f32, target: f32, delta: f32) -> f32 {
+    if value < target {
+        (value + delta).min(target)
+    } else {
+        (value - delta).max(target)
+    }
+}
+
 system_impl!(
     A,
     B,
@@ -155,45 +295,173 @@ system_impl!(
     D,
     (timer, Timer),
     (game_input, graphics::GameInput),
-    (camera, (f32, f32)),
-    (control_point, (f32, f32))
+    (tiles, graphics::Tiles)
 );
-pub fn player_system(
+pub fn platformer_system(
     timer: &mut Timer,
     game_input: &mut graphics::GameInput,
-    camera: &mut (f32, f32),
-    control_point: &mut (f32, f32),
+    tiles: &mut graphics::Tiles,
     aabb: &mut AABB,
     vel: &mut Velocity,
-    sprite: &mut Sprite,
-    player: &mut Player,
+    body: &mut PhysicsBody,
+    controller: &mut PlatformerController,
 ) {
-    if aabb.last & Correction::Up as u8 != 0 {
-        player.can_jump = 0.1;
-    } else if player.can_jump > 0.0 {
-        player.can_jump -= timer.dt();
+    let dt = timer.dt();
+    let contact = tile_contact(aabb, tiles);
+    controller.grounded = aabb.last & Correction::Up as u8 != 0;
+    let input = (game_input.right as i32 - game_input.left as i32) as f32;
+    if input != 0.0 {
+        controller.facing = input;
+    }
+
+    if !contact.climbable {
+        controller.climbing = false;
+    } else if game_input.jump != game_input.crouch && !controller.jumping {
+        controller.climbing = true;
+    }
+    if controller.climbing {
+        let climb = (game_input.jump as i32 - game_input.crouch as i32) as f32;
+        vel.x = input * controller.climb_speed;
+        vel.y = climb * controller.climb_speed;
+        body.gravity_multiplier = 0.0;
+        controller.coyote = 0.0;
+        controller.jump_buffer = 0.0;
+        controller.jump_held = game_input.jump;
+        return;
+    }
+
+    let wall = if aabb.last & Correction::Left as u8 != 0 {
+        -1.0
+    } else if aabb.last & Correction::Right as u8 != 0 {
+        1.0
     } else {
-        player.can_jump = 0.0;
+        0.0
+    };
+
+    if controller.grounded || contact.liquid.is_some() {
+        controller.coyote = controller.coyote_time;
+        controller.jumping = false;
+    } else {
+        controller.coyote = (controller.coyote - dt).max(0.0);
     }
-    vel.y -= (if game_input.crouch { 400.0 } else { 200.0 }) * timer.dt();
-    vel.x = 0.0;
-    if game_input.left {
-        vel.x += -100.0;
+    if wall != 0.0 && !controller.grounded {
+        controller.wall_coyote = controller.coyote_time;
+        controller.wall_dir = wall;
+    } else {
+        controller.wall_coyote = (controller.wall_coyote - dt).max(0.0);
     }
-    if game_input.right {
-        vel.x += 100.0;
+    if game_input.jump && !controller.jump_held {
+        controller.jump_buffer = controller.jump_buffer_time;
+    } else {
+        controller.jump_buffer = (controller.jump_buffer - dt).max(0.0);
+    }
+
+    let rate = match (controller.grounded, input != 0.0) {
+        (true, true) => controller.ground_accel,
+        (true, false) => controller.ground_decel,
+        (false, true) => controller.air_accel,
+        (false, false) => controller.air_decel,
+    } * if controller.grounded {
+        contact.friction.unwrap_or(1.0)
+    } else {
+        1.0
+    };
+    vel.x = approach(vel.x, input * controller.run_speed, rate * dt);
+
+    if wall != 0.0 && !controller.grounded && input == wall {
+        vel.y = vel.y.max(-controller.wall_slide_speed);
+    }
+
+    if controller.jump_buffer > 0.0 {
+        if controller.coyote > 0.0 {
+            vel.y = controller.jump_speed;
+            controller.coyote = 0.0;
+            controller.jump_buffer = 0.0;
+            controller.jumping = true;
+        } else if controller.wall_coyote > 0.0 {
+            vel.x = -controller.wall_dir * controller.wall_jump_speed.0;
+            vel.y = controller.wall_jump_speed.1;
+            controller.wall_coyote = 0.0;
+            controller.jump_buffer = 0.0;
+            controller.jumping = true;
+        }
+    }
+    if controller.jumping && (!game_input.jump || vel.y <= 0.0) {
+        if vel.y > 0.0 {
+            vel.y *= controller.jump_cut;
+        }
+        controller.jumping = false;
+    }
+    controller.jump_held = game_input.jump;
+
+    body.gravity_multiplier = if game_input.crouch {
+        controller.crouch_gravity_scale
+    } else {
+        1.0
+    };
+}
+
+system_impl!(
+    A,
+    B,
+    (timer, Timer),
+    (tiles, graphics::Tiles),
+    (camera, Camera)
+);
+pub fn hazard_system(
+    timer: &mut Timer,
+    tiles: &mut graphics::Tiles,
+    camera: &mut Camera,
+    aabb: &mut AABB,
+    health: &mut Health,
+) {
+    health.cooldown = (health.cooldown - timer.dt()).max(0.0);
+    let damage = tile_contact(aabb, tiles).damage;
+    if damage > 0.0 && health.cooldown <= 0.0 {
+        health.current = (health.current - damage).max(0.0);
+        health.cooldown = health.invulnerability;
+        camera.add_trauma(0.5);
     }
-    if game_input.jump && player.can_jump > 0.0 {
-        vel.y = 100.0;
-        player.can_jump = 0.0;
+}
+
+system_impl!(A, B, (timer, Timer));
+pub fn animation_system(timer: &mut Timer, sprite: &mut Sprite, animator: &mut Animator) {
+    let sheet = graphics::sprite_sheets().get(sprite.sprite);
+    if let Some(frame) = animator.update(sheet, timer.dt()) {
+        sprite.frame = frame;
     }
-    if player.can_jump > 0.0 {
-        sprite.frame = 0;
+}
+
+system_impl!(
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    (camera, Camera),
+    (control_point, (f32, f32))
+);
+#[allow(clippy::too_many_arguments)]
+pub fn player_system(
+    camera: &mut Camera,
+    control_point: &mut (f32, f32),
+    aabb: &mut AABB,
+    vel: &mut Velocity,
+    sprite: &mut Sprite,
+    animator: &mut Animator,
+    controller: &mut PlatformerController,
+    _player: &mut Player,
+) {
+    if !controller.grounded {
+        animator.play("jump");
+    } else if vel.x.abs() > 1.0 {
+        animator.play("run");
     } else {
-        sprite.frame = 1;
+        animator.play("idle");
     }
-    camera.0 = aabb.x;
-    camera.1 = aabb.y;
+    sprite.flip_x = controller.facing < 0.0;
+    camera.follow(aabb.x, aabb.y, (vel.x, vel.y));
     control_point.0 = aabb.x;
     control_point.1 = aabb.y;
 }
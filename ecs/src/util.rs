@@ -16,6 +16,10 @@ extern crate graphics;
 
 use super::components::*;
 
+/// Bit flags OR'd into `AABB::last` by `correct_collision`. `Left` and `Right`
+/// are set when the mover is stopped by something on that side of it. `Up` is
+/// set when it is pushed up out of something below (landing on the ground) and
+/// `Down` when it is pushed down by a ceiling.
 #[repr(u8)]
 #[derive(PartialEq, Eq, Debug)]
 pub enum Correction {
@@ -28,11 +32,36 @@ pub enum Correction {
 
 const SMALL_POS: f32 = 0.00001;
 
+fn overlaps(pos1: f32, size1: f32, pos2: f32, size2: f32) -> bool {
+    2.0 * (pos1 - pos2).abs() <= size1 + size2
+}
+
+/// Returns true if the two boxes overlap or touch. Edges that exactly meet count
+/// as a collision. Symmetric in its arguments.
 pub fn collides(aabb1: &AABB, aabb2: &AABB) -> bool {
-    2.0 * (aabb1.x - aabb2.x).abs() <= aabb1.w + aabb2.w
-        && 2.0 * (aabb1.y - aabb2.y).abs() <= aabb1.h + aabb2.h
+    overlaps(aabb1.x, aabb1.w, aabb2.x, aabb2.w) && overlaps(aabb1.y, aabb1.h, aabb2.y, aabb2.h)
 }
 
+/// Resolves a single moving box against a single static box.
+///
+/// `aabb1_after` is the mover's position at the end of the step, after it has
+/// travelled `vel1 * dt`. `vel1` must be the velocity relative to `aabb2`, and
+/// the mover is expected to have started the step clear of `aabb2`.
+///
+/// If the swept box enters `aabb2` during the step, the mover is pulled back
+/// along the axis it entered on, to `SMALL_POS` short of contact, and the
+/// matching `Correction` flag is OR'd into `aabb1_after.last`. When both axes
+/// enter at the same instant the vertical axis wins. The other axis keeps its
+/// end-of-step value, so the box slides along the surface it hit.
+///
+/// Guarantees:
+/// - Afterwards the boxes no longer overlap, up to floating point error.
+/// - At most one flag is added per call, and it matches the side the mover
+///   approached from.
+/// - The corrected position lies between the start and end of the step, so a
+///   correction never moves the box further than it travelled.
+/// - If the swept box never touches `aabb2`, nothing is changed.
+/// - `aabb2`, `w` and `h` are never modified.
 pub fn correct_collision(aabb1_after: &mut AABB, aabb2: &mut AABB, vel1: (f32, f32), dt: f32) {
     let aabb1 = AABB {
         x: aabb1_after.x - vel1.0 * dt,
@@ -45,63 +74,34 @@ pub fn correct_collision(aabb1_after: &mut AABB, aabb2: &mut AABB, vel1: (f32, f
     let time_to_right_coll = (aabb2.x - aabb1.x - (aabb1.w + aabb2.w) / 2.0) / vel1.0;
     let time_to_up_coll = (aabb2.y - aabb1.y + (aabb1.h + aabb2.h) / 2.0) / vel1.1;
     let time_to_down_coll = (aabb2.y - aabb1.y - (aabb1.h + aabb2.h) / 2.0) / vel1.1;
-    let left_collides = vel1.0 != 0.0
-        && time_to_left_coll >= 0.0
-        && time_to_left_coll <= dt
-        && collides(
-            &AABB {
-                x: aabb1_after.x.max(aabb1.x + vel1.0 * time_to_left_coll),
-                y: aabb1_after.y,
-                w: aabb1.w,
-                h: aabb1.h,
-                last: 0,
-            },
-            aabb2,
-        );
-    let right_collides = vel1.0 != 0.0
-        && time_to_right_coll >= 0.0
-        && time_to_right_coll <= dt
-        && collides(
-            &AABB {
-                x: aabb1_after.x.min(aabb1.x + vel1.0 * time_to_right_coll),
-                y: aabb1_after.y,
-                w: aabb1.w,
-                h: aabb1.h,
-                last: 0,
-            },
-            aabb2,
-        );
-    let up_collides = vel1.1 != 0.0
-        && time_to_up_coll >= 0.0
-        && time_to_up_coll <= dt
-        && collides(
-            &AABB {
-                x: aabb1_after.x,
-                y: aabb1_after.y.max(aabb1.y + vel1.1 * time_to_up_coll),
-                w: aabb1.w,
-                h: aabb1.h,
-                last: 0,
-            },
-            aabb2,
-        );
-    let down_collides = vel1.1 != 0.0
-        && time_to_down_coll >= 0.0
-        && time_to_down_coll <= dt
-        && collides(
-            &AABB {
-                x: aabb1_after.x,
-                y: aabb1_after.y.min(aabb1.y + vel1.1 * time_to_down_coll),
-                w: aabb1.w,
-                h: aabb1.h,
-                last: 0,
-            },
-            aabb2,
-        );
-    if left_collides
-        && (!right_collides || time_to_left_coll < time_to_right_coll)
-        && (!up_collides || time_to_left_coll < time_to_up_coll)
-        && (!down_collides || time_to_left_coll < time_to_down_coll)
-    {
+    let x_window = if vel1.0 < 0.0 {
+        Some((time_to_left_coll, time_to_right_coll))
+    } else if vel1.0 > 0.0 {
+        Some((time_to_right_coll, time_to_left_coll))
+    } else if overlaps(aabb1.x, aabb1.w, aabb2.x, aabb2.w) {
+        Some((f32::NEG_INFINITY, f32::INFINITY))
+    } else {
+        None
+    };
+    let y_window = if vel1.1 < 0.0 {
+        Some((time_to_up_coll, time_to_down_coll))
+    } else if vel1.1 > 0.0 {
+        Some((time_to_down_coll, time_to_up_coll))
+    } else if overlaps(aabb1.y, aabb1.h, aabb2.y, aabb2.h) {
+        Some((f32::NEG_INFINITY, f32::INFINITY))
+    } else {
+        None
+    };
+    let ((x_entry, x_exit), (y_entry, y_exit)) = match (x_window, y_window) {
+        (Some(x), Some(y)) => (x, y),
+        _ => return,
+    };
+    let entry = x_entry.max(y_entry);
+    if entry < 0.0 || entry > dt || entry > x_exit.min(y_exit) {
+        return;
+    }
+
+    if x_entry > y_entry && vel1.0 < 0.0 {
         *aabb1_after = AABB {
             x: aabb1_after
                 .x
@@ -111,11 +111,7 @@ pub fn correct_collision(aabb1_after: &mut AABB, aabb2: &mut AABB, vel1: (f32, f
             h: aabb1.h,
             last: aabb1_after.last | Correction::Left as u8,
         };
-    } else if right_collides
-        && (!left_collides || time_to_right_coll < time_to_left_coll)
-        && (!up_collides || time_to_right_coll < time_to_up_coll)
-        && (!down_collides || time_to_right_coll < time_to_down_coll)
-    {
+    } else if x_entry > y_entry && vel1.0 > 0.0 {
         *aabb1_after = AABB {
             x: aabb1_after
                 .x
@@ -125,11 +121,7 @@ pub fn correct_collision(aabb1_after: &mut AABB, aabb2: &mut AABB, vel1: (f32, f
             h: aabb1.h,
             last: aabb1_after.last | Correction::Right as u8,
         };
-    } else if up_collides
-        && (!left_collides || time_to_up_coll < time_to_left_coll)
-        && (!right_collides || time_to_up_coll < time_to_right_coll)
-        && (!down_collides || time_to_up_coll < time_to_down_coll)
-    {
+    } else if vel1.1 < 0.0 {
         *aabb1_after = AABB {
             x: aabb1_after.x,
             y: aabb1_after
@@ -139,11 +131,7 @@ pub fn correct_collision(aabb1_after: &mut AABB, aabb2: &mut AABB, vel1: (f32, f
             h: aabb1.h,
             last: aabb1_after.last | Correction::Up as u8,
         };
-    } else if down_collides
-        && (!left_collides || time_to_down_coll < time_to_left_coll)
-        && (!right_collides || time_to_down_coll < time_to_right_coll)
-        && (!up_collides || time_to_down_coll < time_to_up_coll)
-    {
+    } else if vel1.1 > 0.0 {
         *aabb1_after = AABB {
             x: aabb1_after.x,
             y: aabb1_after
@@ -156,6 +144,14 @@ pub fn correct_collision(aabb1_after: &mut AABB, aabb2: &mut AABB, vel1: (f32, f
     }
 }
 
+/// Returns every tile in a loaded chunk whose cell overlaps `aabb`, plus a
+/// margin of at least one tile on each side. Only collision layers are
+/// consulted, see `Tiles::collision_at`. Empty cells are reported as
+/// `Tile::NO_TILE`, and cells in chunks that are not loaded are skipped.
+///
+/// Results are ordered row by row, bottom to top, and left to right within a
+/// row. `update_aabb` relies on this order to merge horizontal runs of solid
+/// tiles.
 pub fn get_all_tiles_in_aabb(
     aabb: &AABB,
     tiles: &graphics::Tiles,
@@ -171,10 +167,82 @@ pub fn get_all_tiles_in_aabb(
         ((aabb.y + aabb.h / 2.0) as i64 + graphics::TILE_SIZE as i64) / graphics::TILE_SIZE as i64;
     for j in min_j..=max_j {
         for i in min_i..=max_i {
-            if let Some((tile, _)) = tiles.at(i, j) {
-                vec.push((*tile, i, j));
+            if let Some(tile) = tiles.collision_at(i, j) {
+                vec.push((tile, i, j));
+            }
+        }
+    }
+    vec
+}
+
+const GROUND_PROBE: f32 = 1.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TileContact {
+    pub damage: f32,
+    pub climbable: bool,
+    pub liquid: Option<graphics::Liquid>,
+    pub friction: Option<f32>,
+}
+
+/// Returns every non-empty tile on a collision layer whose cell overlaps
+/// `aabb`, solid or not, together with its registry properties. Tiles the
+/// registry doesn't know are skipped.
+pub fn tiles_touching<'a>(
+    aabb: &AABB,
+    tiles: &'a graphics::Tiles,
+) -> Vec<(graphics::Tile, i64, i64, &'a graphics::TileProperties)> {
+    let tile_size = graphics::TILE_SIZE as f32;
+    let min_i = ((aabb.x - aabb.w / 2.0) / tile_size).floor() as i64;
+    let min_j = ((aabb.y - aabb.h / 2.0) / tile_size).floor() as i64;
+    let max_i = ((aabb.x + aabb.w / 2.0) / tile_size).ceil() as i64 - 1;
+    let max_j = ((aabb.y + aabb.h / 2.0) / tile_size).ceil() as i64 - 1;
+    let mut vec = vec![];
+    for layer in tiles.layers.iter().filter(|layer| layer.collision) {
+        for j in min_j..=max_j {
+            for i in min_i..=max_i {
+                let (tile, _) = layer.get(i, j);
+                if let Some(properties) = tiles.properties(tile) {
+                    vec.push((tile, i, j, properties));
+                }
             }
         }
     }
     vec
 }
+
+/// Summarises the tiles overlapping `aabb`: the highest damage, whether any
+/// of them is climbable and the most buoyant liquid. `friction` comes from
+/// the tiles just below the bottom edge, the surface the box would stand on.
+pub fn tile_contact(aabb: &AABB, tiles: &graphics::Tiles) -> TileContact {
+    let mut contact = TileContact::default();
+    for (_, _, _, properties) in tiles_touching(aabb, tiles) {
+        contact.damage = contact.damage.max(properties.damage);
+        contact.climbable |= properties.climbable;
+        if let Some(liquid) = properties.liquid {
+            if contact
+                .liquid
+                .is_none_or(|other| liquid.buoyancy > other.buoyancy)
+            {
+                contact.liquid = Some(liquid);
+            }
+        }
+    }
+    let feet = AABB {
+        x: aabb.x,
+        y: aabb.y - aabb.h / 2.0 - GROUND_PROBE / 2.0,
+        w: aabb.w,
+        h: GROUND_PROBE,
+        last: 0,
+    };
+    for (_, _, _, properties) in tiles_touching(&feet, tiles) {
+        if let Some(friction) = properties.friction.filter(|_| properties.solid) {
+            contact.friction = Some(
+                contact
+                    .friction
+                    .map_or(friction, |other| other.min(friction)),
+            );
+        }
+    }
+    contact
+}
@@ -12,86 +12,160 @@
  * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::collections::HashMap;
-
+use super::camera::*;
 use super::components::*;
 use super::resources::*;
+use super::streaming::*;
 use super::systems::*;
 
+extern crate audio;
 extern crate graphics;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Entity {
     pub index: usize,
+    pub generation: u32,
 }
 
 pub struct Components {
+    pub generations: Vec<u32>,
     pub aabbs: Vec<Option<AABB>>,
     pub velocities: Vec<Option<Velocity>>,
+    pub bodies: Vec<Option<PhysicsBody>>,
     pub sprites: Vec<Option<Sprite>>,
     pub players: Vec<Option<Player>>,
+    pub controllers: Vec<Option<PlatformerController>>,
+    pub kinematics: Vec<Option<Kinematic>>,
+    pub healths: Vec<Option<Health>>,
+    pub animators: Vec<Option<Animator>>,
 }
 
 pub struct Resources {
     pub timer: Timer,
+    pub gravity: f32,
     pub sprite_batch_res: SpriteBatchRes,
+    pub text_batch_res: TextBatchRes,
     pub game_input: graphics::GameInput,
-    pub camera: (f32, f32),
+    pub camera: Camera,
     pub control_point: (f32, f32),
     pub tiles: graphics::Tiles,
+    pub solids: Solids,
+    pub sounds: Option<audio::Sounds>,
 }
 
 pub struct World {
     pub components: Components,
     pub size: usize,
+    pub free: Vec<usize>,
     pub systems: Vec<Box<dyn System>>,
     pub resources: Resources,
+    pub streamer: Option<Streamer>,
 }
 
 impl World {
     pub fn new() -> Self {
         Self {
             components: Components {
+                generations: Vec::new(),
                 aabbs: Vec::new(),
                 velocities: Vec::new(),
+                bodies: Vec::new(),
                 sprites: Vec::new(),
                 players: Vec::new(),
+                controllers: Vec::new(),
+                kinematics: Vec::new(),
+                healths: Vec::new(),
+                animators: Vec::new(),
             },
             size: 0,
+            free: Vec::new(),
             systems: Vec::new(),
             resources: Resources {
                 timer: Timer::new(),
+                gravity: 200.0,
                 sprite_batch_res: SpriteBatchRes::new(0 as *mut graphics::SpriteBatch),
+                text_batch_res: TextBatchRes::new(std::ptr::null_mut()),
                 game_input: graphics::GameInput::new(),
-                camera: (0.0, 0.0),
+                camera: Default::default(),
                 control_point: (0.0, 0.0),
-                tiles: graphics::Tiles(HashMap::new()),
+                tiles: Default::default(),
+                solids: Default::default(),
+                sounds: None,
             },
+            streamer: None,
         }
     }
 
     pub fn add(&mut self) -> Entity {
+        if let Some(index) = self.free.pop() {
+            return Entity {
+                index,
+                generation: self.components.generations[index],
+            };
+        }
+        self.components.generations.push(0);
         self.components.aabbs.push(None);
         self.components.velocities.push(None);
+        self.components.bodies.push(None);
         self.components.sprites.push(None);
         self.components.players.push(None);
-        let entity = Entity { index: self.size };
+        self.components.controllers.push(None);
+        self.components.kinematics.push(None);
+        self.components.healths.push(None);
+        self.components.animators.push(None);
+        let entity = Entity {
+            index: self.size,
+            generation: 0,
+        };
         self.size += 1;
         entity
     }
 
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.components.generations.get(entity.index) == Some(&entity.generation)
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if !self.is_alive(entity) {
+            return;
+        }
+        self.components.generations[entity.index] = entity.generation.wrapping_add(1);
+        self.components.aabbs[entity.index] = None;
+        self.components.velocities[entity.index] = None;
+        self.components.bodies[entity.index] = None;
+        self.components.sprites[entity.index] = None;
+        self.components.players[entity.index] = None;
+        self.components.controllers[entity.index] = None;
+        self.components.kinematics[entity.index] = None;
+        self.components.healths[entity.index] = None;
+        self.components.animators[entity.index] = None;
+        self.free.push(entity.index);
+    }
+
     pub fn insert<T: Component>(&mut self, entity: Entity, component: T) {
+        if !self.is_alive(entity) {
+            return;
+        }
         let vec = T::get_host_vec(&mut self.components);
         vec[entity.index] = Some(component);
     }
 
+    pub fn get<T: Component>(&mut self, entity: Entity) -> Option<&mut T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        T::get_host_vec(&mut self.components)[entity.index].as_mut()
+    }
+
     pub fn run(
         &mut self,
         input: graphics::GameInput,
+        viewport: (u32, u32),
     ) -> (
         graphics::SpriteBatch,
         graphics::TileBatch,
-        f32,
+        graphics::TextBatch,
+        graphics::View,
         f32,
         f32,
         f32,
@@ -99,40 +173,55 @@ impl World {
         self.resources.timer.update_dt();
         let mut sprite_batch: graphics::SpriteBatch = Default::default();
         self.resources.sprite_batch_res = SpriteBatchRes::new(&mut sprite_batch);
+        let mut text_batch: graphics::TextBatch = Vec::new();
+        self.resources.text_batch_res = TextBatchRes::new(&mut text_batch);
         self.resources.game_input = input;
+        self.resources.solids.0.clear();
+        self.resources.tiles.publish_changes();
+        if let Some(sounds) = self.resources.sounds.as_mut() {
+            sounds.reload();
+        }
+
+        if let Some(mut streamer) = self.streamer.take() {
+            streamer.update(self);
+            self.streamer = Some(streamer);
+        }
 
         for system in self.systems.iter_mut() {
-            for entity in 0..self.size {
+            if !system.per_entity() {
                 system.run(
                     &mut self.components,
-                    Entity { index: entity },
+                    Entity {
+                        index: 0,
+                        generation: 0,
+                    },
                     &mut self.resources,
                 );
+                continue;
             }
-        }
-
-        let mut tile_batch: graphics::TileBatch = Default::default();
-        for (coords, data) in self.resources.tiles.0.iter() {
-            for r in 0..graphics::CHUNK_SIZE {
-                for c in 0..graphics::CHUNK_SIZE {
-                    if data[r][c].0 != graphics::Tile::NoTile {
-                        tile_batch[data[r][c].0 as usize].push((
-                            data[r][c].1,
-                            coords.0 * graphics::CHUNK_SIZE as i64 + r as i64,
-                            coords.1 * graphics::CHUNK_SIZE as i64 + c as i64,
-                        ));
-                    }
-                }
+            for index in 0..self.size {
+                let entity = Entity {
+                    index,
+                    generation: self.components.generations[index],
+                };
+                system.run(&mut self.components, entity, &mut self.resources);
             }
         }
 
+        let tile_batch = self.resources.tiles.batch();
+        let view = self
+            .resources
+            .camera
+            .update(self.resources.timer.dt(), viewport);
+
         (
             sprite_batch,
             tile_batch,
-            self.resources.camera.0,
-            self.resources.camera.1,
+            text_batch,
+            view,
             self.resources.control_point.0,
             self.resources.control_point.1,
+            self.resources.timer.seconds(),
         )
     }
 }
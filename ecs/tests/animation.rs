@@ -0,0 +1,92 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use ecs::*;
+use graphics::{Clip, PlayMode, SpriteSheet};
+
+fn sheet(mode: PlayMode) -> SpriteSheet {
+    SpriteSheet {
+        sprite: graphics::Sprite::TestSprite1,
+        image: String::from("gen/test-sprite1.png"),
+        frames: 5,
+        clips: vec![Clip {
+            name: String::from("walk"),
+            first: 2,
+            durations: vec![0.1, 0.2, 0.1],
+            mode,
+            events: vec![(1, String::from("step"))],
+        }],
+    }
+}
+
+fn frames(animator: &mut Animator, sheet: &SpriteSheet, steps: usize) -> Vec<usize> {
+    (0..steps)
+        .map(|_| animator.update(sheet, 0.1).unwrap())
+        .collect()
+}
+
+#[test]
+fn loop_respects_durations() {
+    let sheet = sheet(PlayMode::Loop);
+    let mut animator = Animator::new("walk");
+    assert_eq!(frames(&mut animator, &sheet, 6), [3, 3, 4, 2, 3, 3]);
+}
+
+#[test]
+fn ping_pong_reverses_at_the_ends() {
+    let sheet = sheet(PlayMode::PingPong);
+    let mut animator = Animator::new("walk");
+    assert_eq!(frames(&mut animator, &sheet, 8), [3, 3, 4, 3, 3, 2, 3, 3]);
+}
+
+#[test]
+fn once_holds_the_last_frame() {
+    let sheet = sheet(PlayMode::Once);
+    let mut animator = Animator::new("walk");
+    assert_eq!(frames(&mut animator, &sheet, 6), [3, 3, 4, 4, 4, 4]);
+    assert!(animator.finished);
+}
+
+#[test]
+fn events_fire_on_entering_a_frame() {
+    let sheet = sheet(PlayMode::Loop);
+    let mut animator = Animator::new("walk");
+    let mut fired = Vec::new();
+    for _ in 0..8 {
+        animator.update(&sheet, 0.1);
+        fired.push(animator.events.len());
+    }
+    assert_eq!(fired, [1, 0, 0, 0, 1, 0, 0, 0]);
+}
+
+#[test]
+fn play_only_restarts_on_a_new_clip() {
+    let sheet = sheet(PlayMode::Loop);
+    let mut animator = Animator::new("walk");
+    frames(&mut animator, &sheet, 2);
+    animator.play("walk");
+    assert_eq!(animator.frame, 1);
+    animator.play("missing");
+    assert_eq!(animator.update(&sheet, 0.1), None);
+    animator.play("walk");
+    assert_eq!(animator.update(&sheet, 0.0), Some(2));
+}
+
+#[test]
+fn player_clips_exist() {
+    let sheet = graphics::sprite_sheets().get(graphics::Sprite::TestSprite1);
+    for clip in ["idle", "run", "jump"] {
+        assert!(sheet.clip(clip).is_some(), "missing clip {}", clip);
+    }
+}
@@ -0,0 +1,182 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use proptest::prelude::*;
+
+use ecs::*;
+
+const TOLERANCE: f32 = 0.001;
+
+fn aabb(x: f32, y: f32, w: f32, h: f32) -> AABB {
+    AABB {
+        x,
+        y,
+        w,
+        h,
+        last: 0,
+    }
+}
+
+fn penetration(a: &AABB, b: &AABB) -> f32 {
+    ((a.w + b.w) / 2.0 - (a.x - b.x).abs()).min((a.h + b.h) / 2.0 - (a.y - b.y).abs())
+}
+
+fn arb_aabb() -> impl Strategy<Value = AABB> {
+    (
+        -200.0f32..200.0,
+        -200.0f32..200.0,
+        1.0f32..48.0,
+        1.0f32..48.0,
+    )
+        .prop_map(|(x, y, w, h)| aabb(x, y, w, h))
+}
+
+fn arb_move() -> impl Strategy<Value = (AABB, AABB, (f32, f32), f32)> {
+    (
+        arb_aabb(),
+        arb_aabb(),
+        (-400.0f32..400.0, -400.0f32..400.0),
+        0.001f32..0.1,
+    )
+        .prop_filter("mover must start clear of the obstacle", |(a, b, v, dt)| {
+            let start = aabb(a.x - v.0 * dt, a.y - v.1 * dt, a.w, a.h);
+            penetration(&start, b) < -TOLERANCE
+        })
+}
+
+// Used to miss the landing: the contact time was re-checked with `collides`,
+// which rounded to just out of contact.
+#[test]
+fn lands_when_contact_time_rounds() {
+    let mut a = aabb(28.05129, 118.4599, 31.105125, 36.61618);
+    let mut b = aabb(8.837025, 95.837524, 13.505891, 37.67555);
+    correct_collision(&mut a, &mut b, (0.0, -351.2856), 0.060357165);
+    assert!(
+        penetration(&a, &b) <= TOLERANCE,
+        "{:?} still overlaps {:?}",
+        a,
+        b
+    );
+    assert_eq!(a.last, Correction::Up as u8);
+}
+
+// Used to be stopped on the side. It is level with the obstacle horizontally
+// before it drops onto it, so it has to land on top instead.
+#[test]
+fn resolves_corner_on_last_axis_entered() {
+    let mut a = aabb(3.0, 5.0, 8.0, 8.0);
+    let mut b = aabb(0.0, 0.0, 16.0, 16.0);
+    correct_collision(&mut a, &mut b, (-100.0, -100.0), 0.1);
+    assert!(
+        penetration(&a, &b) <= TOLERANCE,
+        "{:?} still overlaps {:?}",
+        a,
+        b
+    );
+    assert_eq!((a.x, a.last), (3.0, Correction::Up as u8));
+}
+
+proptest! {
+    #[test]
+    fn collides_is_symmetric(a in arb_aabb(), b in arb_aabb()) {
+        prop_assert_eq!(collides(&a, &b), collides(&b, &a));
+    }
+
+    #[test]
+    fn collides_matches_penetration(a in arb_aabb(), b in arb_aabb()) {
+        prop_assert_eq!(collides(&a, &b), penetration(&a, &b) >= 0.0);
+    }
+
+    #[test]
+    fn correction_removes_overlap((mut a, mut b, vel, dt) in arb_move()) {
+        correct_collision(&mut a, &mut b, vel, dt);
+        prop_assert!(penetration(&a, &b) <= TOLERANCE, "{:?} still overlaps {:?}", a, b);
+    }
+
+    #[test]
+    fn correction_flag_matches_approach((mut a, mut b, vel, dt) in arb_move()) {
+        let start = aabb(a.x - vel.0 * dt, a.y - vel.1 * dt, a.w, a.h);
+        correct_collision(&mut a, &mut b, vel, dt);
+        if a.last & Correction::Left as u8 != 0 {
+            prop_assert!(vel.0 < 0.0);
+            prop_assert!(start.x - start.w / 2.0 >= b.x + b.w / 2.0 - TOLERANCE);
+        }
+        if a.last & Correction::Right as u8 != 0 {
+            prop_assert!(vel.0 > 0.0);
+            prop_assert!(start.x + start.w / 2.0 <= b.x - b.w / 2.0 + TOLERANCE);
+        }
+        if a.last & Correction::Up as u8 != 0 {
+            prop_assert!(vel.1 < 0.0);
+            prop_assert!(start.y - start.h / 2.0 >= b.y + b.h / 2.0 - TOLERANCE);
+        }
+        if a.last & Correction::Down as u8 != 0 {
+            prop_assert!(vel.1 > 0.0);
+            prop_assert!(start.y + start.h / 2.0 <= b.y - b.h / 2.0 + TOLERANCE);
+        }
+        prop_assert!(a.last.count_ones() <= 1);
+    }
+
+    #[test]
+    fn correction_stays_within_travel((mut a, mut b, vel, dt) in arb_move()) {
+        let end = a;
+        let start = aabb(a.x - vel.0 * dt, a.y - vel.1 * dt, a.w, a.h);
+        correct_collision(&mut a, &mut b, vel, dt);
+        prop_assert!(a.x >= start.x.min(end.x) - TOLERANCE && a.x <= start.x.max(end.x) + TOLERANCE);
+        prop_assert!(a.y >= start.y.min(end.y) - TOLERANCE && a.y <= start.y.max(end.y) + TOLERANCE);
+        prop_assert_eq!((a.w, a.h), (end.w, end.h));
+    }
+
+    #[test]
+    fn correction_ignores_clear_moves((mut a, mut b, vel, dt) in arb_move()) {
+        let end = a;
+        let swept = aabb(
+            a.x - vel.0 * dt / 2.0,
+            a.y - vel.1 * dt / 2.0,
+            a.w + (vel.0 * dt).abs(),
+            a.h + (vel.1 * dt).abs(),
+        );
+        prop_assume!(penetration(&swept, &b) < -TOLERANCE);
+        correct_collision(&mut a, &mut b, vel, dt);
+        prop_assert_eq!((a.x, a.y, a.last), (end.x, end.y, 0));
+    }
+
+    #[test]
+    fn tiles_in_aabb_cover_overlapping_tiles(
+        a in arb_aabb(),
+        solid in proptest::collection::vec((-16i64..16, -16i64..16), 0..64),
+    ) {
+        let mut tiles: graphics::Tiles = Default::default();
+        let layer = tiles.layer_mut(graphics::MAIN_LAYER).unwrap();
+        for (x, y) in solid.iter() {
+            layer.set(*x, *y, (graphics::Tile(1), 0));
+        }
+        let found = get_all_tiles_in_aabb(&a, &tiles);
+        let tile_size = graphics::TILE_SIZE as f32;
+        for (x, y) in solid.iter() {
+            let tile = aabb(
+                (*x as f32 + 0.5) * tile_size,
+                (*y as f32 + 0.5) * tile_size,
+                tile_size,
+                tile_size,
+            );
+            if collides(&a, &tile) {
+                prop_assert!(found.contains(&(graphics::Tile(1), *x, *y)));
+            }
+        }
+        for window in found.windows(2) {
+            let ((_, x1, y1), (_, x2, y2)) = (window[0], window[1]);
+            prop_assert!(y1 < y2 || (y1 == y2 && x1 < x2));
+        }
+    }
+}
@@ -0,0 +1,312 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::Duration;
+
+use ecs::*;
+use graphics::{GameInput, Liquid, Tile, TileProperties, Tiles, MAIN_LAYER};
+
+const GROUND: Tile = Tile(1);
+const SPIKES: Tile = Tile(3);
+const LADDER: Tile = Tile(4);
+const WATER: Tile = Tile(5);
+const SHALLOWS: Tile = Tile(6);
+const UNKNOWN: Tile = Tile(9);
+
+// Ground along y = 0 from x = 0 to 7, with one of each special tile on top:
+// spikes at x = 1, a ladder at x = 3, water at x = 5 and 6 and an unknown
+// tile at x = 7.
+fn level() -> Tiles {
+    let mut tiles = Tiles::default();
+    let passable = TileProperties {
+        solid: false,
+        ..Default::default()
+    };
+    for (tile, properties) in [
+        (
+            GROUND,
+            TileProperties {
+                friction: Some(0.5),
+                ..Default::default()
+            },
+        ),
+        (
+            SPIKES,
+            TileProperties {
+                damage: 10.0,
+                ..passable.clone()
+            },
+        ),
+        (
+            LADDER,
+            TileProperties {
+                climbable: true,
+                ..passable.clone()
+            },
+        ),
+        (
+            WATER,
+            TileProperties {
+                liquid: Some(Liquid {
+                    buoyancy: 2.0,
+                    drag: 1.0,
+                }),
+                ..passable.clone()
+            },
+        ),
+        (
+            SHALLOWS,
+            TileProperties {
+                liquid: Some(Liquid {
+                    buoyancy: 0.5,
+                    drag: 1.0,
+                }),
+                ..passable.clone()
+            },
+        ),
+    ] {
+        tiles.properties.insert(tile, properties);
+    }
+    let layer = tiles.layer_mut(MAIN_LAYER).unwrap();
+    for x in 0..8 {
+        layer.set(x, 0, (GROUND, 0));
+    }
+    for (x, y, tile) in [
+        (1, 1, SPIKES),
+        (3, 1, LADDER),
+        (3, 2, LADDER),
+        (5, 1, WATER),
+        (6, 1, SHALLOWS),
+        (7, 1, UNKNOWN),
+    ] {
+        layer.set(x, y, (tile, 0));
+    }
+    tiles
+}
+
+// An 8x8 box resting on the ground in column `x`.
+fn standing_in(x: i64) -> AABB {
+    AABB {
+        x: (x as f32 + 0.5) * graphics::TILE_SIZE as f32,
+        y: 20.0,
+        w: 8.0,
+        h: 8.0,
+        last: 0,
+    }
+}
+
+fn tick() -> Timer {
+    let mut timer = Timer::new();
+    std::thread::sleep(Duration::from_millis(10));
+    timer.update_dt();
+    timer
+}
+
+fn body() -> PhysicsBody {
+    PhysicsBody {
+        gravity_scale: 1.0,
+        gravity_multiplier: 1.0,
+        drag: 0.0,
+        max_fall_speed: 300.0,
+        friction: None,
+        restitution: None,
+        impact: (0.0, 0.0),
+    }
+}
+
+#[test]
+fn touching_skips_edges_and_unknown_tiles() {
+    let tiles = level();
+    let touching: Vec<_> = tiles_touching(&standing_in(3), &tiles)
+        .into_iter()
+        .map(|(tile, x, y, _)| (tile, x, y))
+        .collect();
+    assert_eq!(touching, [(LADDER, 3, 1)]);
+
+    assert!(tiles_touching(&standing_in(7), &tiles).is_empty());
+
+    let mut straddling = standing_in(5);
+    straddling.x += 8.0;
+    let touching: Vec<_> = tiles_touching(&straddling, &tiles)
+        .into_iter()
+        .map(|(tile, ..)| tile)
+        .collect();
+    assert_eq!(touching, [WATER, SHALLOWS]);
+}
+
+#[test]
+fn contact_summarises_the_overlapping_tiles() {
+    let tiles = level();
+    let spikes = tile_contact(&standing_in(1), &tiles);
+    assert_eq!(spikes.damage, 10.0);
+    assert!(!spikes.climbable);
+    assert_eq!(spikes.friction, Some(0.5));
+
+    assert!(tile_contact(&standing_in(3), &tiles).climbable);
+
+    let mut straddling = standing_in(5);
+    straddling.x += 8.0;
+    let water = tile_contact(&straddling, &tiles);
+    assert_eq!(water.liquid.unwrap().buoyancy, 2.0);
+    assert_eq!(water.damage, 0.0);
+
+    let mut floating = standing_in(2);
+    floating.y += 4.0;
+    assert_eq!(tile_contact(&floating, &tiles).friction, None);
+}
+
+#[test]
+fn hazards_damage_once_per_cooldown() {
+    let mut tiles = level();
+    let mut camera = Camera::default();
+    let mut aabb = standing_in(1);
+    let mut health = Health {
+        current: 100.0,
+        max: 100.0,
+        invulnerability: 5.0,
+        cooldown: 0.0,
+    };
+
+    let mut timer = tick();
+    hazard_system(&mut timer, &mut tiles, &mut camera, &mut aabb, &mut health);
+    assert_eq!(health.current, 90.0);
+    assert_eq!(health.cooldown, 5.0);
+    assert!(camera.trauma > 0.0);
+
+    let mut timer = tick();
+    hazard_system(&mut timer, &mut tiles, &mut camera, &mut aabb, &mut health);
+    assert_eq!(health.current, 90.0);
+    assert!(health.cooldown < 5.0);
+
+    health.invulnerability = 0.001;
+    health.cooldown = 0.001;
+    let mut timer = tick();
+    hazard_system(&mut timer, &mut tiles, &mut camera, &mut aabb, &mut health);
+    assert_eq!(health.current, 80.0);
+
+    let mut safe = standing_in(2);
+    let mut timer = tick();
+    hazard_system(&mut timer, &mut tiles, &mut camera, &mut safe, &mut health);
+    assert_eq!(health.current, 80.0);
+}
+
+#[test]
+fn ladders_hold_climbers_until_they_leave() {
+    let mut tiles = level();
+    let mut aabb = standing_in(3);
+    let mut vel = Velocity { x: 0.0, y: 0.0 };
+    let mut body = body();
+    let mut controller = PlatformerController::default();
+    let mut input = GameInput {
+        jump: true,
+        ..GameInput::new()
+    };
+
+    let mut timer = tick();
+    platformer_system(
+        &mut timer,
+        &mut input,
+        &mut tiles,
+        &mut aabb,
+        &mut vel,
+        &mut body,
+        &mut controller,
+    );
+    assert!(controller.climbing);
+    assert_eq!(vel.y, controller.climb_speed);
+    assert_eq!(body.gravity_multiplier, 0.0);
+
+    let mut input = GameInput::new();
+    platformer_system(
+        &mut timer,
+        &mut input,
+        &mut tiles,
+        &mut aabb,
+        &mut vel,
+        &mut body,
+        &mut controller,
+    );
+    assert!(controller.climbing);
+    assert_eq!(vel.y, 0.0);
+
+    let mut aabb = standing_in(2);
+    platformer_system(
+        &mut timer,
+        &mut input,
+        &mut tiles,
+        &mut aabb,
+        &mut vel,
+        &mut body,
+        &mut controller,
+    );
+    assert!(!controller.climbing);
+    assert_eq!(body.gravity_multiplier, 1.0);
+}
+
+#[test]
+fn friction_only_slows_bodies_on_the_floor() {
+    let mut tiles = level();
+    let mut timer = tick();
+    let mut gravity = 200.0;
+    let mut slide = |last: Correction| {
+        let mut aabb = standing_in(2);
+        aabb.last = last as u8;
+        let mut vel = Velocity { x: 50.0, y: 0.0 };
+        let mut body = PhysicsBody {
+            friction: Some(1.0),
+            ..body()
+        };
+        physics_system(
+            &mut timer,
+            &mut gravity,
+            &mut tiles,
+            &mut aabb,
+            &mut vel,
+            &mut body,
+        );
+        vel.x
+    };
+
+    assert!(slide(Correction::Up) < 50.0);
+    assert_eq!(slide(Correction::Down), 50.0);
+    assert_eq!(slide(Correction::None), 50.0);
+}
+
+#[test]
+fn liquids_push_bodies_up_and_slow_them() {
+    let mut tiles = level();
+    let mut timer = tick();
+    let mut gravity = 200.0;
+    let mut fall = |x: i64| {
+        let mut aabb = standing_in(x);
+        aabb.y += 4.0;
+        let mut vel = Velocity { x: 50.0, y: 0.0 };
+        physics_system(
+            &mut timer,
+            &mut gravity,
+            &mut tiles,
+            &mut aabb,
+            &mut vel,
+            &mut body(),
+        );
+        vel
+    };
+
+    let air = fall(2);
+    let water = fall(5);
+    assert!(air.y < 0.0);
+    assert!(water.y > 0.0, "{:?}", water);
+    assert_eq!(air.x, 50.0);
+    assert!(water.x < 50.0);
+}
@@ -0,0 +1,182 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::Duration;
+
+use ecs::*;
+use graphics::Tiles;
+
+const TOLERANCE: f32 = 0.001;
+
+fn assert_near(actual: (f32, f32), expected: (f32, f32)) {
+    assert!(
+        (actual.0 - expected.0).abs() < TOLERANCE && (actual.1 - expected.1).abs() < TOLERANCE,
+        "{:?} != {:?}",
+        actual,
+        expected
+    );
+}
+
+fn tick() -> Timer {
+    let mut timer = Timer::new();
+    std::thread::sleep(Duration::from_millis(10));
+    timer.update_dt();
+    timer
+}
+
+fn aabb(x: f32, y: f32, w: f32, h: f32) -> AABB {
+    AABB {
+        x,
+        y,
+        w,
+        h,
+        last: 0,
+    }
+}
+
+// A 16x16 platform at the origin moving along `path`, stepped once.
+fn moved_platform(timer: &mut Timer, solids: &mut Solids, path: KinematicPath) -> Solid {
+    let mut platform = aabb(0.0, 0.0, 16.0, 16.0);
+    let mut kinematic = Kinematic {
+        path,
+        time: 0.0,
+        vel: (0.0, 0.0),
+    };
+    kinematic_system(timer, solids, &mut platform, &mut kinematic);
+    solids.0[0]
+}
+
+fn line(to: (f32, f32), speed: f32) -> KinematicPath {
+    KinematicPath::Waypoints {
+        points: vec![(0.0, 0.0), to],
+        speed,
+        looped: false,
+    }
+}
+
+#[test]
+fn waypoints_interpolate_and_turn_back() {
+    let path = KinematicPath::Waypoints {
+        points: vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)],
+        speed: 10.0,
+        looped: false,
+    };
+    assert_near(path.position(0.0), (0.0, 0.0));
+    assert_near(path.position(0.5), (5.0, 0.0));
+    assert_near(path.position(1.5), (10.0, 5.0));
+    assert_near(path.position(2.0), (10.0, 10.0));
+    assert_near(path.position(2.5), (10.0, 5.0));
+    assert_near(path.position(3.5), (5.0, 0.0));
+    assert_near(path.position(4.5), (5.0, 0.0));
+}
+
+#[test]
+fn looped_waypoints_return_to_the_start() {
+    let path = KinematicPath::Waypoints {
+        points: vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)],
+        speed: 10.0,
+        looped: true,
+    };
+    assert_near(path.position(3.5), (0.0, 5.0));
+    assert_near(path.position(4.0), (0.0, 0.0));
+    assert_near(path.position(4.5), (5.0, 0.0));
+
+    let single = KinematicPath::Waypoints {
+        points: vec![(3.0, 4.0)],
+        speed: 10.0,
+        looped: true,
+    };
+    assert_near(single.position(7.0), (3.0, 4.0));
+}
+
+#[test]
+fn sine_paths_oscillate_around_their_origin() {
+    let path = KinematicPath::Sine {
+        origin: (1.0, 2.0),
+        amplitude: (3.0, 0.0),
+        period: 4.0,
+        phase: 0.0,
+    };
+    assert_near(path.position(0.0), (1.0, 2.0));
+    assert_near(path.position(1.0), (4.0, 2.0));
+    assert_near(path.position(3.0), (-2.0, 2.0));
+    assert_near(path.position(4.0), (1.0, 2.0));
+}
+
+#[test]
+fn kinematics_publish_their_velocity_as_solids() {
+    let mut timer = tick();
+    let mut solids = Solids::default();
+    let solid = moved_platform(&mut timer, &mut solids, line((100.0, 0.0), 50.0));
+    assert_eq!(solids.0.len(), 1);
+    assert_near(solid.vel, (50.0, 0.0));
+    assert_near((solid.aabb.x, solid.aabb.y), (50.0 * timer.dt(), 0.0));
+}
+
+#[test]
+fn kinematics_push_bodies_out_of_their_way() {
+    let mut timer = tick();
+    let mut solids = Solids::default();
+    let solid = moved_platform(&mut timer, &mut solids, line((100.0, 0.0), 100.0));
+
+    // Clear of the platform before it moved, overlapping it after.
+    let mut body = aabb(solid.aabb.x + 11.5, 0.0, 8.0, 8.0);
+    let mut vel = Velocity { x: -20.0, y: 0.0 };
+    body.x -= vel.x * timer.dt();
+    update_aabb(
+        &mut timer,
+        &mut Tiles::default(),
+        &mut solids,
+        &mut body,
+        &mut vel,
+    );
+    assert_ne!(body.last & Correction::Left as u8, 0);
+    assert!(body.x >= solid.aabb.x + 12.0, "{:?}", body);
+    assert_eq!(vel.x, 0.0);
+}
+
+#[test]
+fn kinematics_carry_their_riders() {
+    for (to, speed) in [((100.0, 0.0), 50.0), ((0.0, 100.0), 50.0)] {
+        let mut timer = tick();
+        let mut solids = Solids::default();
+        let solid = moved_platform(&mut timer, &mut solids, line(to, speed));
+        let moved = (solid.aabb.x, solid.aabb.y);
+
+        let mut rider = aabb(0.0, 12.0, 8.0, 8.0);
+        rider.last = Correction::Up as u8;
+        let mut vel = Velocity { x: 0.0, y: 0.0 };
+        update_aabb(
+            &mut timer,
+            &mut Tiles::default(),
+            &mut solids,
+            &mut rider,
+            &mut vel,
+        );
+        assert_near((rider.x, rider.y), (moved.0, 12.0 + moved.1));
+
+        let mut jumper = aabb(0.0, 12.0, 8.0, 8.0);
+        update_aabb(
+            &mut timer,
+            &mut Tiles::default(),
+            &mut solids,
+            &mut jumper,
+            &mut vel,
+        );
+        // Without ground contact nothing is carried; a rising lift still
+        // pushes the box up out of it.
+        assert_eq!(jumper.x, 0.0);
+        assert!(jumper.y >= 12.0 + moved.1 - TOLERANCE, "{:?}", jumper);
+    }
+}
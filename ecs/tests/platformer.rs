@@ -0,0 +1,153 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::Duration;
+
+use ecs::*;
+use graphics::{GameInput, Tiles};
+
+const GROUNDED: u8 = Correction::Up as u8;
+const AIRBORNE: u8 = Correction::None as u8;
+const WALL: u8 = Correction::Left as u8;
+
+struct Jumper {
+    vel: Velocity,
+    body: PhysicsBody,
+    controller: PlatformerController,
+}
+
+impl Jumper {
+    fn new() -> Self {
+        Self {
+            vel: Velocity { x: 0.0, y: 0.0 },
+            body: PhysicsBody {
+                gravity_scale: 1.0,
+                gravity_multiplier: 1.0,
+                drag: 0.0,
+                max_fall_speed: 300.0,
+                friction: None,
+                restitution: None,
+                impact: (0.0, 0.0),
+            },
+            controller: PlatformerController::default(),
+        }
+    }
+
+    // Runs one ~10ms frame with `last` as the previous frame's collision
+    // corrections, standing in for the physics system.
+    fn step(&mut self, jump: bool, last: u8) {
+        let mut timer = Timer::new();
+        std::thread::sleep(Duration::from_millis(10));
+        timer.update_dt();
+        let mut input = GameInput {
+            jump,
+            ..GameInput::new()
+        };
+        let mut aabb = AABB {
+            x: 0.0,
+            y: 0.0,
+            w: 8.0,
+            h: 8.0,
+            last,
+        };
+        platformer_system(
+            &mut timer,
+            &mut input,
+            &mut Tiles::default(),
+            &mut aabb,
+            &mut self.vel,
+            &mut self.body,
+            &mut self.controller,
+        );
+    }
+}
+
+#[test]
+fn jumps_pressed_just_before_landing_are_buffered() {
+    let mut player = Jumper::new();
+    player.step(true, AIRBORNE);
+    assert_eq!(player.vel.y, 0.0);
+    assert!(player.controller.jump_buffer > 0.0);
+
+    player.step(true, GROUNDED);
+    assert_eq!(player.vel.y, player.controller.jump_speed);
+    assert_eq!(player.controller.jump_buffer, 0.0);
+    assert!(player.controller.jumping);
+
+    let mut late = Jumper::new();
+    late.controller.jump_buffer_time = 0.001;
+    late.step(true, AIRBORNE);
+    late.step(true, AIRBORNE);
+    late.step(true, GROUNDED);
+    assert_eq!(late.vel.y, 0.0);
+}
+
+#[test]
+fn jumps_just_after_leaving_a_ledge_still_count() {
+    let mut player = Jumper::new();
+    player.step(false, GROUNDED);
+    assert_eq!(player.controller.coyote, player.controller.coyote_time);
+
+    player.step(true, AIRBORNE);
+    assert_eq!(player.vel.y, player.controller.jump_speed);
+    assert_eq!(player.controller.coyote, 0.0);
+
+    let mut late = Jumper::new();
+    late.controller.coyote_time = 0.001;
+    late.step(false, GROUNDED);
+    late.step(false, AIRBORNE);
+    late.step(true, AIRBORNE);
+    assert_eq!(late.vel.y, 0.0);
+    assert!(late.controller.jump_buffer > 0.0);
+}
+
+#[test]
+fn jumps_against_a_wall_push_away_from_it() {
+    let mut player = Jumper::new();
+    player.step(false, WALL);
+    assert_eq!(player.controller.wall_dir, -1.0);
+    assert!(player.controller.wall_coyote > 0.0);
+
+    player.step(true, AIRBORNE);
+    let (x, y) = player.controller.wall_jump_speed;
+    assert_eq!(player.vel.x, x);
+    assert_eq!(player.vel.y, y);
+    assert_eq!(player.controller.wall_coyote, 0.0);
+
+    let mut grounded = Jumper::new();
+    grounded.step(false, WALL | GROUNDED);
+    assert_eq!(grounded.controller.wall_coyote, 0.0);
+    grounded.step(true, WALL | GROUNDED);
+    assert_eq!(grounded.vel.x, 0.0);
+    assert_eq!(grounded.vel.y, grounded.controller.jump_speed);
+}
+
+#[test]
+fn releasing_jump_early_cuts_the_jump_short() {
+    let mut held = Jumper::new();
+    held.step(true, GROUNDED);
+    held.step(true, AIRBORNE);
+    assert_eq!(held.vel.y, held.controller.jump_speed);
+    assert!(held.controller.jumping);
+
+    let mut released = Jumper::new();
+    released.step(true, GROUNDED);
+    released.step(false, AIRBORNE);
+    let speed = released.controller.jump_speed;
+    assert_eq!(released.vel.y, speed * released.controller.jump_cut);
+    assert!(!released.controller.jumping);
+
+    released.step(false, AIRBORNE);
+    assert_eq!(released.vel.y, speed * released.controller.jump_cut);
+}
@@ -0,0 +1,240 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use ecs::*;
+use graphics::{Tile, Tiles, MAIN_LAYER};
+
+const TOLERANCE: f32 = 0.001;
+const DIAGONAL: (f32, f32) = (1.0, -1.0);
+
+fn aabb(x: f32, y: f32, w: f32, h: f32) -> AABB {
+    AABB {
+        x,
+        y,
+        w,
+        h,
+        last: 0,
+    }
+}
+
+fn assert_hit(hit: Option<RayHit>, x: f32, y: f32, distance: f32, normal: (f32, f32)) {
+    let hit = hit.expect("expected a hit");
+    assert!(
+        (hit.x - x).abs() < TOLERANCE
+            && (hit.y - y).abs() < TOLERANCE
+            && (hit.distance - distance).abs() < TOLERANCE,
+        "{:?}",
+        hit
+    );
+    assert_eq!(hit.normal, normal);
+}
+
+// A floor along y = 0 from x = -4 to 3 and a wall at x = 4 from y = 0 to 3.
+fn level() -> Tiles {
+    let mut tiles = Tiles::default();
+    let layer = tiles.layer_mut(MAIN_LAYER).unwrap();
+    for x in -4..4 {
+        layer.set(x, 0, (Tile(1), 0));
+    }
+    for y in 0..4 {
+        layer.set(4, y, (Tile(2), 0));
+    }
+    tiles
+}
+
+fn raycast(origin: (f32, f32), dir: (f32, f32), max_dist: f32) -> Option<(i64, i64, RayHit)> {
+    raycast_tiles(origin, dir, max_dist, &level()).map(|(_, x, y, hit)| (x, y, hit))
+}
+
+#[test]
+fn ray_hits_tiles_along_axes() {
+    let (x, y, hit) = raycast((8.0, 40.0), (0.0, -1.0), 100.0).unwrap();
+    assert_eq!((x, y), (0, 0));
+    assert_hit(Some(hit), 8.0, 16.0, 24.0, (0.0, 1.0));
+
+    let (x, y, hit) = raycast((8.0, 24.0), (1.0, 0.0), 100.0).unwrap();
+    assert_eq!((x, y), (4, 1));
+    assert_hit(Some(hit), 64.0, 24.0, 56.0, (-1.0, 0.0));
+
+    let (tile, ..) = raycast_tiles((8.0, 24.0), (1.0, 0.0), 100.0, &level()).unwrap();
+    assert_eq!(tile, Tile(2));
+}
+
+#[test]
+fn ray_hits_tiles_diagonally() {
+    let (x, y, hit) = raycast((4.0, 40.0), DIAGONAL, 100.0).unwrap();
+    assert_eq!((x, y), (1, 0));
+    assert_hit(Some(hit), 28.0, 16.0, 24.0 * 2f32.sqrt(), (0.0, 1.0));
+}
+
+#[test]
+fn ray_misses_tiles_out_of_reach() {
+    assert!(raycast((8.0, 40.0), (0.0, 1.0), 100.0).is_none());
+    assert!(raycast((8.0, 40.0), (0.0, -1.0), 20.0).is_none());
+    assert!(raycast((8.0, 40.0), (0.0, 0.0), 100.0).is_none());
+}
+
+#[test]
+fn ray_without_a_limit_stops_past_the_loaded_tiles() {
+    assert!(raycast((8.0, 40.0), (0.0, 1.0), f32::INFINITY).is_none());
+    assert!(raycast((-100.0, 40.0), (-1.0, 0.01), f32::MAX).is_none());
+    let (x, y, hit) = raycast((-1000.0, 8.0), (1.0, 0.0), f32::INFINITY).unwrap();
+    assert_eq!((x, y), (-4, 0));
+    assert_hit(Some(hit), -64.0, 8.0, 936.0, (-1.0, 0.0));
+    assert!(raycast_tiles((8.0, 8.0), (1.0, 0.0), f32::INFINITY, &Tiles::default()).is_none());
+}
+
+#[test]
+fn ray_starting_inside_a_tile_hits_at_once() {
+    let (x, y, hit) = raycast((8.0, 8.0), (0.0, -1.0), 100.0).unwrap();
+    assert_eq!((x, y), (0, 0));
+    assert_hit(Some(hit), 8.0, 8.0, 0.0, (0.0, 0.0));
+}
+
+#[test]
+fn ray_hits_boxes() {
+    let target = aabb(0.0, 0.0, 16.0, 16.0);
+    assert_hit(
+        raycast_aabb((-20.0, 0.0), (1.0, 0.0), 100.0, &target),
+        -8.0,
+        0.0,
+        12.0,
+        (-1.0, 0.0),
+    );
+    assert_hit(
+        raycast_aabb((0.0, 20.0), (0.0, -1.0), 100.0, &target),
+        0.0,
+        8.0,
+        12.0,
+        (0.0, 1.0),
+    );
+    assert_hit(
+        raycast_aabb((-4.0, 20.0), DIAGONAL, 100.0, &target),
+        8.0,
+        8.0,
+        12.0 * 2f32.sqrt(),
+        (0.0, 1.0),
+    );
+    assert!(raycast_aabb((-20.0, 0.0), (1.0, 0.0), 10.0, &target).is_none());
+    assert!(raycast_aabb((-20.0, 0.0), (-1.0, 0.0), 100.0, &target).is_none());
+}
+
+#[test]
+fn ray_starting_inside_a_box_hits_at_once() {
+    let target = aabb(0.0, 0.0, 16.0, 16.0);
+    assert_hit(
+        raycast_aabb((2.0, 3.0), (1.0, 0.0), 100.0, &target),
+        2.0,
+        3.0,
+        0.0,
+        (0.0, 0.0),
+    );
+}
+
+#[test]
+fn ray_grazing_a_corner_hits() {
+    let target = aabb(8.0, 8.0, 16.0, 16.0);
+    assert_hit(
+        raycast_aabb((-10.0, 6.0), (1.0, 1.0), 100.0, &target),
+        0.0,
+        16.0,
+        10.0 * 2f32.sqrt(),
+        (-1.0, 0.0),
+    );
+    assert!(raycast_aabb((-10.0, 6.5), (1.0, 1.0), 100.0, &target).is_none());
+}
+
+#[test]
+fn zero_length_sweep_only_hits_overlaps() {
+    let target = aabb(0.0, 0.0, 16.0, 16.0);
+    let overlapping = aabb(10.0, 0.0, 8.0, 8.0);
+    assert_hit(
+        sweep_aabb(&overlapping, (0.0, 0.0), &target),
+        10.0,
+        0.0,
+        0.0,
+        (0.0, 0.0),
+    );
+    assert!(sweep_aabb(&aabb(20.0, 0.0, 8.0, 8.0), (0.0, 0.0), &target).is_none());
+
+    let clear = aabb(8.0, 40.0, 8.0, 8.0);
+    assert!(sweep_aabb_tiles(&clear, (0.0, 0.0), &level()).is_none());
+}
+
+#[test]
+fn sweep_stops_at_first_contact() {
+    let target = aabb(0.0, 0.0, 16.0, 16.0);
+    assert_hit(
+        sweep_aabb(&aabb(-20.0, 0.0, 8.0, 8.0), (20.0, 0.0), &target),
+        -12.0,
+        0.0,
+        8.0,
+        (-1.0, 0.0),
+    );
+    assert!(sweep_aabb(&aabb(-20.0, 0.0, 8.0, 8.0), (4.0, 0.0), &target).is_none());
+    assert!(sweep_aabb(&aabb(-20.0, 20.0, 8.0, 8.0), (40.0, 0.0), &target).is_none());
+}
+
+#[test]
+fn sweep_grazing_a_corner_hits() {
+    let target = aabb(0.0, 0.0, 16.0, 16.0);
+    assert_hit(
+        sweep_aabb(&aabb(-22.0, 2.0, 8.0, 8.0), (20.0, 20.0), &target),
+        -12.0,
+        12.0,
+        10.0 * 2f32.sqrt(),
+        (-1.0, 0.0),
+    );
+    assert!(sweep_aabb(&aabb(-22.0, 2.5, 8.0, 8.0), (20.0, 20.0), &target).is_none());
+}
+
+#[test]
+fn sweep_hits_the_nearest_tile() {
+    let falling = aabb(8.0, 40.0, 8.0, 8.0);
+    let (tile, x, y, hit) = sweep_aabb_tiles(&falling, (0.0, -40.0), &level()).unwrap();
+    assert_eq!((tile, x, y), (Tile(1), 0, 0));
+    assert_hit(Some(hit), 8.0, 20.0, 20.0, (0.0, 1.0));
+
+    let walking = aabb(40.0, 24.0, 8.0, 8.0);
+    let (tile, x, y, hit) = sweep_aabb_tiles(&walking, (40.0, 0.0), &level()).unwrap();
+    assert_eq!((tile, x, y), (Tile(2), 4, 1));
+    assert_hit(Some(hit), 60.0, 24.0, 20.0, (-1.0, 0.0));
+}
+
+#[test]
+fn sweep_hits_the_nearest_entity() {
+    let mut world = World::new();
+    let mover = world.add();
+    world.insert(mover, aabb(0.0, 0.0, 16.0, 16.0));
+    let near = world.add();
+    world.insert(near, aabb(40.0, 0.0, 16.0, 16.0));
+    let far = world.add();
+    world.insert(far, aabb(80.0, 0.0, 16.0, 16.0));
+    let moving = aabb(0.0, 0.0, 16.0, 16.0);
+
+    let (entity, hit) =
+        sweep_aabb_entities(&moving, (100.0, 0.0), &world.components, Some(mover)).unwrap();
+    assert_eq!(entity, near);
+    assert_hit(Some(hit), 24.0, 0.0, 24.0, (-1.0, 0.0));
+
+    let (entity, hit) =
+        sweep_aabb_entities(&moving, (100.0, 0.0), &world.components, None).unwrap();
+    assert_eq!(entity, mover);
+    assert_eq!(hit.distance, 0.0);
+
+    world.remove(near);
+    let (entity, _) =
+        sweep_aabb_entities(&moving, (100.0, 0.0), &world.components, Some(mover)).unwrap();
+    assert_eq!(entity, far);
+}
@@ -0,0 +1,216 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use ecs::*;
+use graphics::{Tile, Tiles, MAIN_LAYER};
+
+const CHUNK: f32 = (graphics::CHUNK_SIZE * graphics::TILE_SIZE) as f32;
+const FAR: i64 = 10;
+const GROUND: Tile = Tile(1);
+const STONE: Tile = Tile(2);
+
+fn open(name: &str, entities: &[EntityDesc]) -> (World, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("streaming-{}-{}", name, std::process::id()));
+    _ = std::fs::remove_dir_all(&dir);
+    let mut tiles = Tiles::default();
+    let far = FAR * graphics::CHUNK_SIZE as i64;
+    let one = graphics::CHUNK_SIZE as i64;
+    for x in 0..4 {
+        for chunk in [0, one, far] {
+            tiles.set_autotiled(chunk + x, 0, GROUND);
+        }
+    }
+    save_streamed(&dir, &tiles, entities).unwrap();
+    let mut world = World::new();
+    let config = StreamingConfig {
+        radius: 0,
+        unload_margin: 0,
+        max_loads_per_frame: 4,
+    };
+    Streamer::open(&mut world, &dir, config).unwrap();
+    (world, dir)
+}
+
+// The part of World::run that streaming depends on.
+fn step(world: &mut World) {
+    world.resources.tiles.publish_changes();
+    let mut streamer = world.streamer.take().unwrap();
+    streamer.update(world);
+    world.streamer = Some(streamer);
+}
+
+fn move_to(world: &mut World, chunk: (i64, i64)) {
+    world.resources.camera.position = (
+        (chunk.0 as f32 + 0.5) * CHUNK,
+        (chunk.1 as f32 + 0.5) * CHUNK,
+    );
+    let start = Instant::now();
+    loop {
+        step(world);
+        let resident: Vec<_> = world.streamer.as_ref().unwrap().resident().collect();
+        if resident == [&chunk] {
+            return;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "{:?} never loaded",
+            chunk
+        );
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn tile(world: &World, x: i64, y: i64) -> Tile {
+    world.resources.tiles.layer(MAIN_LAYER).unwrap().get(x, y).0
+}
+
+#[test]
+fn edits_survive_unloading() {
+    let (mut world, dir) = open("unload", &[]);
+    assert_eq!(tile(&world, 1, 0), GROUND);
+    world.resources.tiles.set_tile(MAIN_LAYER, 1, 0, STONE);
+    world.resources.tiles.clear_tile(MAIN_LAYER, 3, 0);
+
+    move_to(&mut world, (FAR, 0));
+    assert_eq!(tile(&world, 1, 0), Tile::NO_TILE);
+    move_to(&mut world, (0, 0));
+    assert_eq!(tile(&world, 0, 0), GROUND);
+    assert_eq!(tile(&world, 1, 0), STONE);
+    assert_eq!(tile(&world, 3, 0), Tile::NO_TILE);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn replayed_edits_apply_to_chunks_loaded_later() {
+    let far = FAR * graphics::CHUNK_SIZE as i64;
+    let mut recorded = Tiles::default();
+    recorded.start_recording();
+    recorded.set_tile(MAIN_LAYER, far + 2, 0, STONE);
+
+    let (mut world, dir) = open("replay", &[]);
+    world.resources.tiles.replay(&recorded.take_recording());
+    move_to(&mut world, (FAR, 0));
+    assert_eq!(tile(&world, far + 1, 0), GROUND);
+    assert_eq!(tile(&world, far + 2, 0), STONE);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+fn platforms(world: &World) -> usize {
+    world.components.kinematics.iter().flatten().count()
+}
+
+#[test]
+fn entities_unload_with_the_chunk_they_are_in() {
+    let platform = EntityDesc::Platform(PlatformDesc {
+        x: 32.0,
+        y: 32.0,
+        w: 32.0,
+        h: 8.0,
+        dx: 0.0,
+        dy: 0.0,
+        period: 1.0,
+    });
+    let (mut world, dir) = open("entities", &[platform]);
+    assert_eq!(platforms(&world), 1);
+    let index = world.components.kinematics.iter().position(Option::is_some);
+    let aabb = world.components.aabbs[index.unwrap()].as_mut().unwrap();
+    aabb.x += CHUNK;
+
+    move_to(&mut world, (1, 0));
+    assert_eq!(platforms(&world), 1);
+    move_to(&mut world, (0, 0));
+    assert_eq!(platforms(&world), 1);
+    move_to(&mut world, (FAR, 0));
+    assert_eq!(platforms(&world), 0);
+    move_to(&mut world, (0, 0));
+    assert_eq!(platforms(&world), 1);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn parked_entities_wait_for_their_chunk() {
+    let platform = EntityDesc::Platform(PlatformDesc {
+        x: 32.0,
+        y: 32.0,
+        w: 32.0,
+        h: 8.0,
+        dx: 0.0,
+        dy: 0.0,
+        period: 1.0,
+    });
+    let (mut world, dir) = open("parked", &[platform]);
+    move_to(&mut world, (FAR, 0));
+    assert_eq!(platforms(&world), 0);
+
+    world.resources.camera.position = (CHUNK / 2.0, CHUNK / 2.0);
+    let start = Instant::now();
+    loop {
+        step(&mut world);
+        let resident: Vec<_> = world.streamer.as_ref().unwrap().resident().collect();
+        if platforms(&world) == 1 {
+            assert_eq!(resident, [&(0, 0)]);
+            break;
+        }
+        assert!(start.elapsed() < Duration::from_secs(5), "never respawned");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn opens_around_the_player() {
+    let far = FAR * graphics::CHUNK_SIZE as i64;
+    let player = EntityDesc::Player(PlayerDesc {
+        x: FAR as f32 * CHUNK + 32.0,
+        y: 32.0,
+    });
+    let (mut world, dir) = open("player", &[player]);
+    assert_eq!(
+        chunk_at(
+            world.resources.camera.position.0,
+            world.resources.camera.position.1
+        ),
+        (FAR, 0)
+    );
+    for _ in 0..3 {
+        step(&mut world);
+        let resident: Vec<_> = world.streamer.as_ref().unwrap().resident().collect();
+        assert_eq!(resident, [&(FAR, 0)]);
+        assert_eq!(tile(&world, far + 1, 0), GROUND);
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn failed_chunk_loads_are_retried() {
+    let (mut world, dir) = open("retry", &[]);
+    let chunk = dir.join("chunks").join(format!("{}_0.bin", FAR));
+    let hidden = dir.join("hidden.bin");
+    std::fs::rename(&chunk, &hidden).unwrap();
+
+    world.resources.camera.position = ((FAR as f32 + 0.5) * CHUNK, CHUNK / 2.0);
+    for _ in 0..20 {
+        step(&mut world);
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(world.streamer.as_ref().unwrap().resident().count(), 0);
+
+    std::fs::rename(&hidden, &chunk).unwrap();
+    move_to(&mut world, (FAR, 0));
+    assert_eq!(tile(&world, FAR * graphics::CHUNK_SIZE as i64, 0), GROUND);
+    std::fs::remove_dir_all(dir).unwrap();
+}
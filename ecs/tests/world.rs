@@ -0,0 +1,62 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use ecs::*;
+
+fn health(current: f32) -> Health {
+    Health {
+        current,
+        max: 100.0,
+        invulnerability: 0.0,
+        cooldown: 0.0,
+    }
+}
+
+#[test]
+fn reused_slots_get_a_new_generation() {
+    let mut world = World::new();
+    let old = world.add();
+    world.remove(old);
+    let new = world.add();
+    assert_eq!(new.index, old.index);
+    assert_ne!(new, old);
+    assert!(!world.is_alive(old));
+    assert!(world.is_alive(new));
+}
+
+#[test]
+fn stale_handles_do_not_touch_the_new_entity() {
+    let mut world = World::new();
+    let old = world.add();
+    world.insert(old, health(10.0));
+    world.remove(old);
+    let new = world.add();
+    world.insert(new, health(50.0));
+
+    world.insert(old, health(1.0));
+    assert!(world.get::<Health>(old).is_none());
+    world.remove(old);
+    assert!(world.is_alive(new));
+    assert_eq!(world.get::<Health>(new).unwrap().current, 50.0);
+}
+
+#[test]
+fn removing_twice_frees_the_slot_once() {
+    let mut world = World::new();
+    let entity = world.add();
+    world.remove(entity);
+    world.remove(entity);
+    let (a, b) = (world.add(), world.add());
+    assert_ne!(a.index, b.index);
+}
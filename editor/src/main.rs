@@ -23,80 +23,38 @@ use std::thread;
 use druid::widget::*;
 use druid::*;
 
+use asset_server::*;
+
+extern crate asset_server;
 extern crate ecs;
 extern crate graphics;
 
-const EDGE_OFFSETS: [(i64, i64); 8] = [
-    (-1, 0),
-    (-1, 1),
-    (0, 1),
-    (1, 1),
-    (1, 0),
-    (1, -1),
-    (0, -1),
-    (-1, -1),
-];
-
-fn calculate_tile_edges(tile_x: i64, tile_y: i64, tiles: &graphics::Tiles) -> u8 {
-    let tile = match tiles.at(tile_x, tile_y) {
-        Some(x) => x.0,
-        None => graphics::Tile::NoTile,
-    };
-
-    let mut acc: u8 = 0;
-    for i in 0..8 {
-        let (o_x, o_y) = EDGE_OFFSETS[i];
-        let o_tile = match tiles.at(o_x + tile_x, o_y + tile_y) {
-            Some(x) => x.0,
-            None => graphics::Tile::NoTile,
-        };
-        acc |= ((o_tile == tile) as u8) << i;
-    }
-    acc
-}
-
 fn save_scene(
-    tiles: &graphics::Tiles,
+    tiles: &mut graphics::Tiles,
     entities: &Vec<ecs::EntityDesc>,
     file_path: &str,
 ) -> std::io::Result<()> {
+    tiles.compact();
     let serialized = bincode::serialize(&(tiles, entities)).unwrap();
     let mut file = File::create(file_path)?;
     file.write(&serialized)?;
     Ok(())
 }
 
-macro_rules! create_tile {
-    ($x:literal) => {
-        ImageBuf::from_raw(
-            image::load_from_memory(&include_bytes!($x)[..])
-                .unwrap()
-                .crop_imm(
-                    4 * graphics::TILE_SIZE as u32,
-                    0,
-                    graphics::TILE_SIZE as u32,
-                    graphics::TILE_SIZE as u32,
-                )
-                .as_bytes(),
-            piet::ImageFormat::RgbaSeparate,
-            graphics::TILE_SIZE,
-            graphics::TILE_SIZE,
-        )
-    };
-}
-
-macro_rules! create_sprite {
-    ($x:literal, $y:expr, $z:expr) => {
-        ImageBuf::from_raw(
-            image::load_from_memory(&include_bytes!($x)[..])
-                .unwrap()
-                .crop_imm($y * $z as u32, 0, $z as u32, $z as u32)
-                .as_bytes(),
-            piet::ImageFormat::RgbaSeparate,
-            $z,
-            $z,
-        )
-    };
+fn icon(assets: &mut AssetServer, path: &str, x: u32) -> ImageBuf {
+    let img = assets
+        .load(path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| image::load_from_memory(&data).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| panic!("Couldn't load {}: {}.", path, e))
+        .crop_imm(x, 0, graphics::TILE_SIZE as u32, graphics::TILE_SIZE as u32)
+        .to_rgba8();
+    ImageBuf::from_raw(
+        img.into_raw(),
+        piet::ImageFormat::RgbaSeparate,
+        graphics::TILE_SIZE,
+        graphics::TILE_SIZE,
+    )
 }
 
 #[derive(Clone, Copy)]
@@ -111,20 +69,38 @@ impl Default for Selection {
     }
 }
 
-const SELECTIONS: [Selection; 4] = [
-    Selection::Tile(graphics::Tile::NoTile),
-    Selection::Tile(graphics::Tile::TestTile1),
-    Selection::Tile(graphics::Tile::TestTile2),
+const ENTITY_SELECTIONS: [Selection; 2] = [
     Selection::Entity(&|x: f32, y: f32| ecs::EntityDesc::Player(ecs::PlayerDesc { x, y })),
+    Selection::Entity(&|x: f32, y: f32| {
+        ecs::EntityDesc::Platform(ecs::PlatformDesc {
+            x,
+            y,
+            w: 32.0,
+            h: 8.0,
+            dx: 0.0,
+            dy: 32.0,
+            period: 4.0,
+        })
+    }),
 ];
 
-fn build_ui() -> impl Widget<()> {
-    let png_data = [
-        create_tile!("../../assets/editor/notile.png"),
-        create_tile!("../../assets/test-tileset1.png"),
-        create_tile!("../../assets/test-tileset2.png"),
-        create_sprite!("../../assets/test-sprite1.png", 0, 16),
-    ];
+fn build_ui(tile_registry: &graphics::TileRegistry) -> impl Widget<()> {
+    let mut assets = AssetServer::new(AssetSource::Directory("assets".into()));
+    let mut selections = vec![Selection::Tile(graphics::Tile::NO_TILE)];
+    let mut png_data = vec![icon(
+        &mut assets,
+        "editor/notile.png",
+        4 * graphics::TILE_SIZE as u32,
+    )];
+    for info in tile_registry.tiles.iter() {
+        selections.push(Selection::Tile(graphics::Tile(info.id)));
+        png_data.push(icon(&mut assets, &info.image, 0));
+    }
+    selections.extend(ENTITY_SELECTIONS);
+    for sprite in [graphics::Sprite::TestSprite1, graphics::Sprite::TestSprite2] {
+        let image = &graphics::sprite_sheets().get(sprite).image;
+        png_data.push(icon(&mut assets, image, 0));
+    }
     let images = png_data.into_iter().map(|png| {
         SizedBox::new(
             Image::new(png)
@@ -157,15 +133,30 @@ fn build_ui() -> impl Widget<()> {
                 Target::Auto,
             ))
         }));
+    let mut layers = Flex::row();
+    for (label, layer) in [
+        ("BG", graphics::BACKGROUND_LAYER),
+        ("Main", graphics::MAIN_LAYER),
+        ("FG", graphics::FOREGROUND_LAYER),
+    ] {
+        layers.add_child(Button::new(label).on_click(move |ctx, _, _| {
+            ctx.submit_command(Command::new(
+                Selector::new("update_layer"),
+                String::from(layer),
+                Target::Auto,
+            ))
+        }));
+    }
+    top.add_child(layers);
     let mut i = 0;
     let mut cur = Flex::row();
-    for image in images {
+    for (selection, image) in selections.into_iter().zip(images) {
         cur.add_child(ControllerHost::new(
             image,
             Click::new(move |ctx, _, _| {
                 ctx.submit_command(Command::new(
                     Selector::new("update_sel"),
-                    SELECTIONS[i],
+                    selection,
                     Target::Auto,
                 ))
             }),
@@ -185,6 +176,7 @@ fn build_ui() -> impl Widget<()> {
 struct Delegate {
     scene: Arc<Mutex<(graphics::Tiles, Vec<ecs::EntityDesc>)>>,
     sel: Arc<Mutex<Selection>>,
+    layer: Arc<Mutex<String>>,
 }
 
 impl AppDelegate<()> for Delegate {
@@ -197,18 +189,21 @@ impl AppDelegate<()> for Delegate {
         _env: &Env,
     ) -> Handled {
         if let Some(file_info) = cmd.get(commands::SAVE_FILE_AS) {
-            let scene = &self.scene.lock().unwrap();
-            if let Err(e) = save_scene(&scene.0, &scene.1, file_info.path().to_str().unwrap()) {
+            let scene = &mut self.scene.lock().unwrap();
+            if let Err(e) = save_scene(&mut scene.0, &scene.1, file_info.path().to_str().unwrap()) {
                 println!("Error writing file: {}", e);
             }
             Handled::Yes
         } else if let Some(file_info) = cmd.get(commands::OPEN_FILE) {
             match std::fs::read(file_info.path()) {
                 Ok(s) => {
-                    let new_scene =
+                    let mut new_scene =
                         bincode::deserialize::<(graphics::Tiles, Vec<ecs::EntityDesc>)>(s.as_ref())
                             .unwrap();
-                    *self.scene.lock().unwrap() = new_scene;
+                    let scene = &mut self.scene.lock().unwrap();
+                    new_scene.0.terrains = std::mem::take(&mut scene.0.terrains);
+                    new_scene.0.properties = std::mem::take(&mut scene.0.properties);
+                    **scene = new_scene;
                 }
                 Err(e) => {
                     println!("Error opening file: {}", e);
@@ -219,6 +214,9 @@ impl AppDelegate<()> for Delegate {
             let mut sel_ref = self.sel.lock().unwrap();
             *sel_ref = *selection;
             Handled::Yes
+        } else if let Some(layer) = cmd.get::<String>(Selector::new("update_layer")) {
+            *self.layer.lock().unwrap() = layer.clone();
+            Handled::Yes
         } else {
             Handled::No
         }
@@ -230,11 +228,16 @@ fn main() {
     let cur_selection: Arc<Mutex<Selection>> = Default::default();
     let scene_clone = Arc::clone(&scene);
     let cur_selection_clone = Arc::clone(&cur_selection);
+    let cur_layer: Arc<Mutex<String>> = Arc::new(Mutex::new(String::from(graphics::MAIN_LAYER)));
+    let cur_layer_clone = Arc::clone(&cur_layer);
+    let tile_registry = graphics::TileRegistry::load("assets/tiles.ron").unwrap();
+    let tile_registry_clone = tile_registry.clone();
+    scene.lock().unwrap().0.apply_registry(&tile_registry);
 
     let (tx, rx): (Sender<()>, Receiver<()>) = mpsc::channel();
     thread::spawn(move || {
         AppLauncher::with_window(
-            WindowDesc::new(|| build_ui())
+            WindowDesc::new(move || build_ui(&tile_registry_clone))
                 .window_size((200.0, 200.0))
                 .resizable(false)
                 .title("Editor Tools"),
@@ -242,6 +245,7 @@ fn main() {
         .delegate(Delegate {
             scene: scene_clone,
             sel: cur_selection_clone,
+            layer: cur_layer_clone,
         })
         .launch(())
         .expect("Failed to editor tools window.");
@@ -253,28 +257,31 @@ fn main() {
     let mut dc: Option<(f32, f32)> = None;
 
     let mut last_click = (false, false, false);
-    pollster::block_on(graphics::Graphics::new()).run(move |controller, _, _, _, _| {
+    let start = std::time::Instant::now();
+    let assets = AssetServer::new(AssetSource::Directory("assets".into()));
+    let window = pollster::block_on(graphics::Graphics::new(&tile_registry, assets));
+    window.run(move |controller, _, _, _| {
         if let Ok(()) = rx.try_recv() {
             process::exit(0);
         }
 
         let scene = &mut scene.lock().unwrap();
+        scene.0.publish_changes();
 
         if controller.left_click {
-            let world_x = (controller.cursor_x as f32 / graphics::PIXEL_SIZE as f32) + cx;
-            let world_y = -(controller.cursor_y as f32 / graphics::PIXEL_SIZE as f32) + cy;
+            let (cursor_x, cursor_y) = controller.cursor();
+            let world_x = cursor_x + cx;
+            let world_y = -cursor_y + cy;
             let selection: &Selection = &cur_selection.lock().unwrap();
             match selection {
                 Selection::Tile(tile) => {
-                    let tile_x = (world_x as i64).div_euclid(graphics::TILE_SIZE as i64);
-                    let tile_y = (world_y as i64).div_euclid(graphics::TILE_SIZE as i64);
-                    *scene.0.get_mut(tile_x, tile_y) = (*tile, 0);
-                    for x in -1..=1 {
-                        for y in -1..=1 {
-                            let (o_x, o_y) = (tile_x + x, tile_y + y);
-                            let frame = calculate_tile_edges(o_x, o_y, &scene.0) as usize;
-                            scene.0.get_mut(o_x, o_y).1 = frame;
-                        }
+                    let layer = cur_layer.lock().unwrap().clone();
+                    if let Some(parallax) = scene.0.layer(&layer).map(|layer| layer.parallax) {
+                        let layer_x = world_x - cx * (1.0 - parallax.0);
+                        let layer_y = world_y - cy * (1.0 - parallax.1);
+                        let tile_x = (layer_x as i64).div_euclid(graphics::TILE_SIZE as i64);
+                        let tile_y = (layer_y as i64).div_euclid(graphics::TILE_SIZE as i64);
+                        scene.0.set_tile(&layer, tile_x, tile_y, *tile);
                     }
                 }
                 Selection::Entity(construct) => {
@@ -290,21 +297,12 @@ fn main() {
 
         if controller.middle_click {
             if let Some((dcx, dcy)) = dc {
-                let (ncx, ncy) = (
-                    controller.cursor_x as f32 / graphics::PIXEL_SIZE as f32,
-                    -controller.cursor_y as f32 / graphics::PIXEL_SIZE as f32,
-                );
+                let (ncx, ncy) = (controller.cursor().0, -controller.cursor().1);
                 cx -= ncx - dcx;
                 cy -= ncy - dcy;
-                dc = Some((
-                    controller.cursor_x as f32 / graphics::PIXEL_SIZE as f32,
-                    -controller.cursor_y as f32 / graphics::PIXEL_SIZE as f32,
-                ));
+                dc = Some((controller.cursor().0, -controller.cursor().1));
             } else {
-                dc = Some((
-                    controller.cursor_x as f32 / graphics::PIXEL_SIZE as f32,
-                    -controller.cursor_y as f32 / graphics::PIXEL_SIZE as f32,
-                ));
+                dc = Some((controller.cursor().0, -controller.cursor().1));
             }
             last_click.1 = true;
         } else {
@@ -312,32 +310,28 @@ fn main() {
             last_click.1 = false;
         }
 
-        let mut tile_batch: graphics::TileBatch = Default::default();
-        for (coords, data) in scene.0 .0.iter() {
-            for r in 0..graphics::CHUNK_SIZE {
-                for c in 0..graphics::CHUNK_SIZE {
-                    if data[r][c].0 != graphics::Tile::NoTile {
-                        tile_batch[data[r][c].0 as usize].push((
-                            data[r][c].1,
-                            coords.0 * graphics::CHUNK_SIZE as i64 + r as i64,
-                            coords.1 * graphics::CHUNK_SIZE as i64 + c as i64,
-                        ));
-                    }
-                }
-            }
-        }
+        let tile_batch = scene.0.batch();
 
         let mut sprite_batch: graphics::SpriteBatch = Default::default();
         for entity_desc in scene.1.iter() {
-            sprite_batch[entity_desc.get_sprite() as usize].push((
-                0,
-                entity_desc.get_pos().0,
-                entity_desc.get_pos().1,
-                1.0,
-                1.0,
-            ));
+            let (x, y) = entity_desc.get_pos();
+            sprite_batch[entity_desc.get_sprite() as usize]
+                .push(graphics::SpriteInstance::new(0, x, y));
         }
+        let text_batch = vec![graphics::Text::screen(
+            &format!("Layer: {}", cur_layer.lock().unwrap()),
+            2.0,
+            2.0,
+        )];
 
-        (sprite_batch, tile_batch, cx, cy, 0.0, 0.0)
+        (
+            sprite_batch,
+            tile_batch,
+            text_batch,
+            graphics::View::new(cx, cy),
+            0.0,
+            0.0,
+            start.elapsed().as_secs_f32(),
+        )
     });
 }
@@ -0,0 +1,120 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::io::{Error, ErrorKind};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use super::sprite::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PlayMode {
+    #[default]
+    Loop,
+    PingPong,
+    Once,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Clip {
+    pub name: String,
+    pub first: usize,
+    pub durations: Vec<f32>,
+    #[serde(default)]
+    pub mode: PlayMode,
+    #[serde(default)]
+    pub events: Vec<(usize, String)>,
+}
+
+impl Clip {
+    pub fn len(&self) -> usize {
+        self.durations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.durations.is_empty()
+    }
+
+    pub fn events_at(&self, frame: usize) -> impl Iterator<Item = &str> {
+        self.events
+            .iter()
+            .filter(move |(at, _)| *at == frame)
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SpriteSheet {
+    pub sprite: Sprite,
+    pub image: String,
+    pub frames: usize,
+    #[serde(default)]
+    pub clips: Vec<Clip>,
+}
+
+impl SpriteSheet {
+    pub fn clip(&self, name: &str) -> Option<&Clip> {
+        self.clips.iter().find(|clip| clip.name == name)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SpriteSheets {
+    pub sheets: Vec<SpriteSheet>,
+}
+
+impl SpriteSheets {
+    pub fn parse(text: &str) -> std::io::Result<Self> {
+        let sheets: SpriteSheets =
+            ron::from_str(text).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        for sprite in Sprite::ALL {
+            if sheets.sheets.iter().filter(|s| s.sprite == sprite).count() != 1 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Sprite {:?} needs exactly one sheet.", sprite),
+                ));
+            }
+        }
+        for sheet in sheets.sheets.iter() {
+            for clip in sheet.clips.iter() {
+                let invalid = |reason: &str| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Clip {} of {:?} {}.", clip.name, sheet.sprite, reason),
+                    )
+                };
+                if clip.is_empty() || clip.durations.iter().any(|d| *d <= 0.0) {
+                    return Err(invalid("has an empty frame"));
+                }
+                if clip.first + clip.len() > sheet.frames {
+                    return Err(invalid("runs past the end of the sheet"));
+                }
+                if clip.events.iter().any(|(frame, _)| *frame >= clip.len()) {
+                    return Err(invalid("has an event past its last frame"));
+                }
+            }
+        }
+        Ok(sheets)
+    }
+
+    pub fn get(&self, sprite: Sprite) -> &SpriteSheet {
+        self.sheets.iter().find(|s| s.sprite == sprite).unwrap()
+    }
+}
+
+pub fn sprite_sheets() -> &'static SpriteSheets {
+    static SHEETS: OnceLock<SpriteSheets> = OnceLock::new();
+    SHEETS.get_or_init(|| SpriteSheets::parse(include_str!("../../assets/sprites.ron")).unwrap())
+}
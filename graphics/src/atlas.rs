@@ -0,0 +1,207 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::io::{Error, ErrorKind};
+
+use image::RgbaImage;
+
+pub const ATLAS_PADDING: u32 = 1;
+pub const MIN_ATLAS_WIDTH: u32 = 1024;
+
+/// Where an image ended up in the atlas. `uv` covers the first band. An image
+/// wider than a page is cut into bands of `band_columns` columns each, and the
+/// bands are stacked `band_stride` apart in v.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AtlasRegion {
+    pub page: usize,
+    pub uv: [f32; 4],
+    pub size: (u32, u32),
+    pub band_columns: usize,
+    pub band_stride: f32,
+}
+
+impl AtlasRegion {
+    pub fn cell(&self, column: usize, columns: usize, rows: usize) -> [f32; 4] {
+        self.grid_cell(column, 0, columns, rows)
+    }
+
+    pub fn grid_cell(&self, column: usize, row: usize, columns: usize, rows: usize) -> [f32; 4] {
+        let band_columns = if self.band_columns == 0 {
+            columns
+        } else {
+            self.band_columns
+        };
+        let w = self.uv[2] / band_columns as f32;
+        let h = self.uv[3] / rows as f32;
+        [
+            self.uv[0] + (column % band_columns) as f32 * w,
+            self.uv[1] + (column / band_columns) as f32 * self.band_stride + row as f32 * h,
+            w,
+            h,
+        ]
+    }
+}
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    x: u32,
+}
+
+struct Page {
+    width: u32,
+    height: u32,
+    shelves: Vec<Shelf>,
+    placed: Vec<(usize, u32, u32)>,
+}
+
+impl Page {
+    fn place(&mut self, w: u32, h: u32, max_size: u32) -> Option<(u32, u32)> {
+        if let Some(shelf) = self
+            .shelves
+            .iter_mut()
+            .find(|shelf| shelf.height >= h && shelf.x + w <= self.width)
+        {
+            let x = shelf.x;
+            shelf.x += w + ATLAS_PADDING;
+            return Some((x, shelf.y));
+        }
+        if self.height + h > max_size || w > self.width {
+            return None;
+        }
+        let y = self.height;
+        self.shelves.push(Shelf {
+            y,
+            height: h,
+            x: w + ATLAS_PADDING,
+        });
+        self.height += h + ATLAS_PADDING;
+        Some((0, y))
+    }
+}
+
+fn too_big(w: u32, h: u32, max_size: u32) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("Image of {}x{} doesn't fit in a {} atlas.", w, h, max_size),
+    )
+}
+
+// Cuts an image wider than `width` into bands of whole columns and stacks them.
+// Returns the stacked image and the number of columns in each band.
+fn split_into_bands(
+    image: &RgbaImage,
+    columns: usize,
+    width: u32,
+    max_size: u32,
+) -> std::io::Result<(RgbaImage, usize)> {
+    let (w, h) = image.dimensions();
+    if w <= width {
+        return Ok((image.clone(), columns));
+    }
+    if columns == 0 || w % columns as u32 != 0 || w / columns as u32 > width {
+        return Err(too_big(w, h, max_size));
+    }
+    let cell = w / columns as u32;
+    let band_columns = (width / cell) as usize;
+    let bands = columns.div_ceil(band_columns) as u32;
+    let band_width = band_columns as u32 * cell;
+    let height = bands * (h + ATLAS_PADDING) - ATLAS_PADDING;
+    if height > max_size {
+        return Err(too_big(w, h, max_size));
+    }
+    let mut stacked = RgbaImage::new(band_width, height);
+    for band in 0..bands {
+        let x = band * band_width;
+        let piece = image::imageops::crop_imm(image, x, 0, band_width.min(w - x), h);
+        image::imageops::replace(
+            &mut stacked,
+            &*piece,
+            0,
+            (band * (h + ATLAS_PADDING)) as i64,
+        );
+    }
+    Ok((stacked, band_columns))
+}
+
+/// Packs `(image, columns)` pairs onto as few pages as fit in `max_size`.
+/// Images wider than a page are split into bands between columns.
+pub fn pack_atlas(
+    images: &[(RgbaImage, usize)],
+    max_size: u32,
+) -> std::io::Result<(Vec<RgbaImage>, Vec<AtlasRegion>)> {
+    let width = images
+        .iter()
+        .map(|(image, _)| image.width())
+        .max()
+        .unwrap_or(1)
+        .max(MIN_ATLAS_WIDTH)
+        .min(max_size);
+    let pieces = images
+        .iter()
+        .map(|(image, columns)| split_into_bands(image, *columns, width, max_size))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    let mut order: Vec<usize> = (0..pieces.len()).collect();
+    order.sort_by_key(|i| std::cmp::Reverse(pieces[*i].0.height()));
+
+    let mut pages: Vec<Page> = Vec::new();
+    for i in order {
+        let (w, h) = pieces[i].0.dimensions();
+        if w > max_size || h > max_size {
+            return Err(too_big(w, h, max_size));
+        }
+        let placed = pages
+            .iter_mut()
+            .enumerate()
+            .find_map(|(p, page)| page.place(w, h, max_size).map(|pos| (p, pos)));
+        let (p, (x, y)) = placed.unwrap_or_else(|| {
+            let mut page = Page {
+                width,
+                height: 0,
+                shelves: Vec::new(),
+                placed: Vec::new(),
+            };
+            let pos = page.place(w, h, max_size).unwrap();
+            pages.push(page);
+            (pages.len() - 1, pos)
+        });
+        pages[p].placed.push((i, x, y));
+    }
+
+    let mut regions = vec![AtlasRegion::default(); images.len()];
+    let mut out = Vec::new();
+    for (p, page) in pages.iter().enumerate() {
+        let height = page.height.max(1);
+        let mut pixels = RgbaImage::new(page.width, height);
+        for (i, x, y) in page.placed.iter() {
+            let (piece, band_columns) = &pieces[*i];
+            let image = &images[*i].0;
+            image::imageops::replace(&mut pixels, piece, *x as i64, *y as i64);
+            regions[*i] = AtlasRegion {
+                page: p,
+                uv: [
+                    *x as f32 / page.width as f32,
+                    *y as f32 / height as f32,
+                    piece.width() as f32 / page.width as f32,
+                    image.height() as f32 / height as f32,
+                ],
+                size: image.dimensions(),
+                band_columns: *band_columns,
+                band_stride: (image.height() + ATLAS_PADDING) as f32 / height as f32,
+            };
+        }
+        out.push(pixels);
+    }
+    Ok((out, regions))
+}
@@ -0,0 +1,69 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+
+use super::tiles::*;
+
+pub const EDGE_OFFSETS: [(i64, i64); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+pub type Terrains = HashMap<Tile, String>;
+
+pub fn connects(a: Tile, b: Tile, terrains: &Terrains) -> bool {
+    if a == b {
+        return true;
+    }
+    match (terrains.get(&a), terrains.get(&b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+impl TileLayer {
+    pub fn tile_edges(&self, x: i64, y: i64, terrains: &Terrains) -> u8 {
+        let tile = self.get(x, y).0;
+        let mut acc: u8 = 0;
+        for (i, (o_x, o_y)) in EDGE_OFFSETS.iter().enumerate() {
+            let o_tile = self.get(x + o_x, y + o_y).0;
+            acc |= (connects(tile, o_tile, terrains) as u8) << i;
+        }
+        acc
+    }
+
+    pub fn set_autotiled(&mut self, x: i64, y: i64, tile: Tile, terrains: &Terrains) {
+        self.fill_autotiled((x, y), (x, y), tile, terrains);
+    }
+}
+
+impl Tiles {
+    pub fn set_autotiled(&mut self, x: i64, y: i64, tile: Tile) {
+        self.set_autotiled_in(MAIN_LAYER, x, y, tile);
+    }
+
+    pub fn set_autotiled_in(&mut self, layer: &str, x: i64, y: i64, tile: Tile) {
+        let terrains = &self.terrains;
+        if let Some(layer) = self.layers.iter_mut().find(|other| other.name == layer) {
+            layer.set_autotiled(x, y, tile, terrains);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+// 
+// This file is part of game-testbed.
+// game-testbed is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// game-testbed is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+// 
+
+struct Params {
+    size: vec2<f32>;
+    scale: f32;
+    sharp: f32;
+};
+[[group(0), binding(0)]]
+var frame_texture: texture_2d<f32>;
+[[group(0), binding(1)]]
+var frame_sampler: sampler;
+[[group(0), binding(2)]]
+var<uniform> params: Params;
+
+struct VertexOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] texcoord: vec2<f32>;
+};
+
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32((index << 1u) & 2u);
+    let y = f32(index & 2u);
+    out.texcoord = vec2<f32>(x, y);
+    out.clip_position = vec4<f32>(x * 2.0 - 1.0, 1.0 - y * 2.0, 0.0, 1.0);
+    return out;
+}
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    let texel = in.texcoord * params.size;
+    var coord: vec2<f32> = floor(texel) + 0.5;
+    if (params.sharp > 0.5) {
+        let region = max(0.5 - 0.5 / params.scale, 0.0);
+        let center = fract(texel) - 0.5;
+        let f = (center - clamp(center, vec2<f32>(-region, -region), vec2<f32>(region, region))) * params.scale + 0.5;
+        coord = floor(texel) + f;
+    }
+    return textureSample(frame_texture, frame_sampler, coord / params.size);
+}
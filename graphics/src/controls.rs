@@ -15,7 +15,7 @@
 use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::event::*;
 
-use super::tiles::*;
+use super::view::*;
 
 const NUM_KEYCODES: usize = 164;
 
@@ -35,6 +35,8 @@ pub struct Controller {
     pub left_click: bool,
     pub right_click: bool,
     pub middle_click: bool,
+    pub viewport: (u32, u32),
+    pub pixel_scale: f32,
     pub scheme: ControllerScheme,
 }
 
@@ -57,6 +59,8 @@ impl Controller {
             left_click: false,
             right_click: false,
             middle_click: false,
+            viewport: (1, 1),
+            pixel_scale: 1.0,
             scheme,
         }
     }
@@ -111,7 +115,14 @@ impl Controller {
         }
     }
 
-    pub fn get_game_input(&self, cx: f32, cy: f32, ax: f32, ay: f32) -> GameInput {
+    pub fn cursor(&self) -> (f32, f32) {
+        (
+            self.cursor_x as f32 / self.pixel_scale,
+            self.cursor_y as f32 / self.pixel_scale,
+        )
+    }
+
+    pub fn get_game_input(&self, view: &View, ax: f32, ay: f32) -> GameInput {
         match self.scheme {
             ControllerScheme::KeyboardMouse {
                 jump_key,
@@ -119,8 +130,9 @@ impl Controller {
                 left_key,
                 right_key,
             } => {
-                let world_dx = (self.cursor_x as f32 / PIXEL_SIZE as f32) + cx - ax;
-                let world_dy = (self.cursor_y as f32 / PIXEL_SIZE as f32) + cy - ay;
+                let (cursor_x, cursor_y) = self.cursor();
+                let (world_x, world_y) = view.screen_to_world(cursor_x, cursor_y);
+                let (world_dx, world_dy) = (world_x - ax, world_y - ay);
                 let magnitude = (world_dx * world_dx + world_dy * world_dy).sqrt();
                 GameInput {
                     jump: self.pressed[jump_key as usize],
@@ -128,7 +140,7 @@ impl Controller {
                     left: self.pressed[left_key as usize],
                     right: self.pressed[right_key as usize],
                     n_cursor_x: world_dx / magnitude,
-                    n_cursor_y: -world_dy / magnitude,
+                    n_cursor_y: world_dy / magnitude,
                 }
             }
         }
@@ -0,0 +1,161 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use serde::{Deserialize, Serialize};
+
+use super::autotile::*;
+use super::tiles::*;
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum TileEdit {
+    Set {
+        layer: String,
+        x: i64,
+        y: i64,
+        tile: Tile,
+    },
+    Fill {
+        layer: String,
+        min: (i64, i64),
+        max: (i64, i64),
+        tile: Tile,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileChanged {
+    pub layer: String,
+    pub x: i64,
+    pub y: i64,
+    pub old: (Tile, usize),
+    pub new: (Tile, usize),
+}
+
+impl TileLayer {
+    pub fn fill_autotiled(
+        &mut self,
+        min: (i64, i64),
+        max: (i64, i64),
+        tile: Tile,
+        terrains: &Terrains,
+    ) -> Vec<TileChanged> {
+        let (min, max) = (
+            (min.0.min(max.0), min.1.min(max.1)),
+            (min.0.max(max.0), min.1.max(max.1)),
+        );
+        let area =
+            || (min.0 - 1..=max.0 + 1).flat_map(|x| (min.1 - 1..=max.1 + 1).map(move |y| (x, y)));
+        let before: Vec<_> = area().map(|(x, y)| self.get(x, y)).collect();
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                self.set(x, y, (tile, 0));
+            }
+        }
+        for (x, y) in area() {
+            let o_tile = self.get(x, y).0;
+            if o_tile != Tile::NO_TILE {
+                let edges = self.tile_edges(x, y, terrains);
+                self.set(x, y, (o_tile, edges as usize));
+            }
+        }
+        area()
+            .zip(before)
+            .filter_map(|((x, y), old)| {
+                let new = self.get(x, y);
+                (new != old).then(|| TileChanged {
+                    layer: self.name.clone(),
+                    x,
+                    y,
+                    old,
+                    new,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Tiles {
+    pub fn edit(&mut self, edit: TileEdit) {
+        let (layer_name, min, max, tile) = match &edit {
+            TileEdit::Set { layer, x, y, tile } => (layer, (*x, *y), (*x, *y), *tile),
+            TileEdit::Fill {
+                layer,
+                min,
+                max,
+                tile,
+            } => (layer, *min, *max, *tile),
+        };
+        let terrains = &self.terrains;
+        let Some(layer) = self
+            .layers
+            .iter_mut()
+            .find(|layer| layer.name == *layer_name)
+        else {
+            return;
+        };
+        let changed = layer.fill_autotiled(min, max, tile, terrains);
+        self.pending_changes.extend(changed);
+        if let Some(recording) = &mut self.recording {
+            recording.push(edit);
+        }
+    }
+
+    pub fn set_tile(&mut self, layer: &str, x: i64, y: i64, tile: Tile) {
+        self.edit(TileEdit::Set {
+            layer: layer.to_string(),
+            x,
+            y,
+            tile,
+        });
+    }
+
+    pub fn clear_tile(&mut self, layer: &str, x: i64, y: i64) {
+        self.set_tile(layer, x, y, Tile::NO_TILE);
+    }
+
+    pub fn fill(&mut self, layer: &str, min: (i64, i64), max: (i64, i64), tile: Tile) {
+        self.edit(TileEdit::Fill {
+            layer: layer.to_string(),
+            min,
+            max,
+            tile,
+        });
+    }
+
+    pub fn clear_region(&mut self, layer: &str, min: (i64, i64), max: (i64, i64)) {
+        self.fill(layer, min, max, Tile::NO_TILE);
+    }
+
+    pub fn changes(&self) -> &[TileChanged] {
+        &self.changes
+    }
+
+    pub fn publish_changes(&mut self) {
+        self.changes = std::mem::take(&mut self.pending_changes);
+    }
+
+    pub fn start_recording(&mut self) {
+        self.recording.get_or_insert_with(Vec::new);
+    }
+
+    pub fn take_recording(&mut self) -> Vec<TileEdit> {
+        self.recording.take().unwrap_or_default()
+    }
+
+    pub fn replay(&mut self, edits: &[TileEdit]) {
+        for edit in edits {
+            self.edit(edit.clone());
+        }
+    }
+}
@@ -12,13 +12,30 @@
  * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
  */
 
-#[macro_use]
-pub mod sprite;
+pub mod animation;
+pub mod atlas;
+pub mod autotile;
 pub mod controls;
+pub mod edit;
+pub mod registry;
+pub mod renderer;
+pub mod sprite;
+pub mod text;
 pub mod tiles;
+pub mod upscale;
+pub mod view;
 pub mod window;
 
+pub use animation::*;
+pub use atlas::*;
+pub use autotile::*;
 pub use controls::*;
+pub use edit::*;
+pub use registry::*;
+pub use renderer::*;
 pub use sprite::*;
+pub use text::*;
 pub use tiles::*;
+pub use upscale::*;
+pub use view::*;
 pub use window::*;
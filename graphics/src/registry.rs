@@ -0,0 +1,172 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::autotile::*;
+use super::tiles::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AnimationSync {
+    #[default]
+    Global,
+    PerTile,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct TileAnimation {
+    pub frames: u32,
+    pub frame_duration: f32,
+    #[serde(default)]
+    pub sync: AnimationSync,
+}
+
+impl TileAnimation {
+    pub fn phase(&self, x: i64, y: i64) -> f32 {
+        match self.sync {
+            AnimationSync::Global => 0.0,
+            AnimationSync::PerTile => {
+                let hash = x.wrapping_mul(73856093) ^ y.wrapping_mul(19349663);
+                hash.rem_euclid(self.frames as i64) as f32 * self.frame_duration
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Liquid {
+    pub buoyancy: f32,
+    pub drag: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TileProperties {
+    pub solid: bool,
+    pub damage: f32,
+    pub climbable: bool,
+    pub liquid: Option<Liquid>,
+    pub friction: Option<f32>,
+    pub values: HashMap<String, String>,
+}
+
+impl Default for TileProperties {
+    fn default() -> Self {
+        TileProperties {
+            solid: true,
+            damage: 0.0,
+            climbable: false,
+            liquid: None,
+            friction: None,
+            values: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TileInfo {
+    pub id: u32,
+    pub name: String,
+    pub image: String,
+    #[serde(default)]
+    pub animation: Option<TileAnimation>,
+    #[serde(default)]
+    pub terrain: Option<String>,
+    #[serde(default)]
+    pub properties: TileProperties,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TileRegistry {
+    pub tiles: Vec<TileInfo>,
+    #[serde(skip)]
+    pub root: PathBuf,
+}
+
+impl TileRegistry {
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let mut registry = Self::parse(&std::fs::read_to_string(path)?)?;
+        registry.root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(registry)
+    }
+
+    pub fn parse(text: &str) -> std::io::Result<Self> {
+        let registry: TileRegistry =
+            ron::from_str(text).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        for (i, info) in registry.tiles.iter().enumerate() {
+            if info.id == Tile::NO_TILE.0 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Tile {} uses the reserved id {}.", info.name, info.id),
+                ));
+            }
+            if let Some(animation) = info.animation {
+                if animation.frames == 0 || animation.frame_duration <= 0.0 {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Tile {} has an empty animation.", info.name),
+                    ));
+                }
+            }
+            if registry.tiles[..i].iter().any(|other| other.id == info.id) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Tile {} reuses id {}.", info.name, info.id),
+                ));
+            }
+        }
+        Ok(registry)
+    }
+
+    pub fn get(&self, tile: Tile) -> Option<&TileInfo> {
+        self.tiles.iter().find(|info| info.id == tile.0)
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<Tile> {
+        self.tiles
+            .iter()
+            .find(|info| info.name == name)
+            .map(|info| Tile(info.id))
+    }
+
+    pub fn terrains(&self) -> Terrains {
+        self.tiles
+            .iter()
+            .filter_map(|info| Some((Tile(info.id), info.terrain.clone()?)))
+            .collect()
+    }
+
+    pub fn properties(&self) -> HashMap<Tile, TileProperties> {
+        self.tiles
+            .iter()
+            .map(|info| (Tile(info.id), info.properties.clone()))
+            .collect()
+    }
+
+    pub fn image_path(&self, info: &TileInfo) -> PathBuf {
+        self.root.join(&info.image)
+    }
+}
+
+impl Tiles {
+    pub fn apply_registry(&mut self, registry: &TileRegistry) {
+        self.terrains = registry.terrains();
+        self.properties = registry.properties();
+    }
+}
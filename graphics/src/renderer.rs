@@ -0,0 +1,757 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use wgpu::util::DeviceExt;
+use wgpu::*;
+
+use asset_server::*;
+
+use super::animation::*;
+use super::atlas::*;
+use super::registry::*;
+use super::sprite::*;
+use super::text::*;
+use super::tiles::*;
+use super::upscale::*;
+use super::view::*;
+
+const INITIAL_INSTANCE_CAPACITY: usize = 4096;
+
+type SpriteDraw = (i32, usize, Range<u32>);
+type AtlasPages = Vec<(super::sprite::Texture, wgpu::BindGroup)>;
+
+pub struct Renderer {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub upscaler: Upscaler,
+    render_pipeline: wgpu::RenderPipeline,
+    vertex_buffers: wgpu::Buffer,
+    assets: AssetServer,
+    atlas: AtlasPages,
+    tile_images: Vec<(Tile, String)>,
+    regions: Regions,
+    tile_animations: HashMap<Tile, TileAnimation>,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    cameras: Vec<(wgpu::Buffer, wgpu::BindGroup)>,
+    screen_camera: (wgpu::Buffer, wgpu::BindGroup),
+    instance_buffer: wgpu::Buffer,
+    instance_capacity: usize,
+    chunk_cache: HashMap<u64, CachedChunk>,
+}
+
+struct Regions {
+    sprites: Vec<AtlasRegion>,
+    font: AtlasRegion,
+    tiles: HashMap<Tile, AtlasRegion>,
+}
+
+struct CachedChunk {
+    instance_buffer: Option<wgpu::Buffer>,
+    draws: Vec<(usize, Range<u32>)>,
+}
+
+impl Renderer {
+    pub fn new(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        format: wgpu::TextureFormat,
+        tile_registry: &TileRegistry,
+        mut assets: AssetServer,
+        display: Display,
+    ) -> Self {
+        let mut tile_animations = HashMap::new();
+        for info in tile_registry.tiles.iter() {
+            if let Some(animation) = info.animation {
+                tile_animations.insert(Tile(info.id), animation);
+            }
+        }
+        let tile_images: Vec<(Tile, String)> = tile_registry
+            .tiles
+            .iter()
+            .map(|info| (Tile(info.id), info.image.clone()))
+            .collect();
+        let (atlas, regions, texture_bind_group_layout) = load_images(&mut assets, &tile_images)
+            .and_then(|images| build_atlas(&device, &queue, &images, &tile_images))
+            .unwrap_or_else(|e| panic!("{}", e));
+
+        let vertex_buffers = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffers"),
+            contents: bytemuck::cast_slice(VERTICES),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let camera_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+                label: Some("camera_bind_group_layout"),
+            });
+
+        let shader = device.create_shader_module(&include_wgsl!("shader.wgsl"));
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &[&texture_bind_group_layout, &camera_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &[
+                            wgpu::VertexAttribute {
+                                offset: 0,
+                                shader_location: 0,
+                                format: wgpu::VertexFormat::Float32x2,
+                            },
+                            wgpu::VertexAttribute {
+                                offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                                shader_location: 1,
+                                format: wgpu::VertexFormat::Float32x2,
+                            },
+                        ],
+                    },
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<super::sprite::Instance>()
+                            as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &super::sprite::Instance::ATTRIBUTES,
+                    },
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        let instance_buffer = create_instance_buffer(&device, INITIAL_INSTANCE_CAPACITY);
+        let upscaler = Upscaler::new(&device, format, display);
+        let screen_camera = create_camera(&device, &camera_bind_group_layout);
+
+        Self {
+            device,
+            queue,
+            upscaler,
+            render_pipeline,
+            vertex_buffers,
+            assets,
+            atlas,
+            tile_images,
+            regions,
+            tile_animations,
+            screen_camera,
+            camera_bind_group_layout,
+            cameras: Vec::new(),
+            instance_buffer,
+            instance_capacity: INITIAL_INSTANCE_CAPACITY,
+            chunk_cache: HashMap::new(),
+        }
+    }
+
+    pub async fn headless(
+        tile_registry: &TileRegistry,
+        assets: AssetServer,
+        display: Display,
+    ) -> Option<Self> {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let mut adapter = None;
+        for force_fallback_adapter in [true, false] {
+            adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::default(),
+                    compatible_surface: None,
+                    force_fallback_adapter,
+                })
+                .await;
+            if adapter.is_some() {
+                break;
+            }
+        }
+        let adapter = adapter?;
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    features: wgpu::Features::empty(),
+                    limits: adapter.limits(),
+                    label: None,
+                },
+                None,
+            )
+            .await
+            .ok()?;
+        Some(Self::new(
+            device,
+            queue,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            tile_registry,
+            assets,
+            display,
+        ))
+    }
+
+    pub fn reload(&mut self) {
+        let changed = self.assets.changed();
+        if changed.is_empty() {
+            return;
+        }
+        match load_images(&mut self.assets, &self.tile_images)
+            .and_then(|images| build_atlas(&self.device, &self.queue, &images, &self.tile_images))
+        {
+            Ok((atlas, regions, _)) => {
+                self.atlas = atlas;
+                self.regions = regions;
+                self.chunk_cache.clear();
+                for path in changed {
+                    log::info!("Reloaded {}", path.display());
+                }
+            }
+            Err(e) => log::error!("Couldn't reload textures: {}", e),
+        }
+    }
+
+    pub fn render_image(
+        &mut self,
+        sprites: &SpriteBatch,
+        tiles: &TileBatch,
+        text: &TextBatch,
+        view: View,
+        time: f32,
+    ) -> image::RgbaImage {
+        let (width, height) = self.viewport();
+        let padded =
+            (4 * width).div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: (padded * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Readback Encoder"),
+            });
+        self.draw(&mut encoder, sprites, tiles, text, view, time);
+        encoder.copy_texture_to_buffer(
+            self.upscaler.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(padded),
+                    rows_per_image: std::num::NonZeroU32::new(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        self.device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(mapping).unwrap();
+        let data = slice.get_mapped_range();
+        let mut pixels = Vec::with_capacity((4 * width * height) as usize);
+        for row in data.chunks(padded as usize) {
+            pixels.extend_from_slice(&row[..(4 * width) as usize]);
+        }
+        drop(data);
+        buffer.unmap();
+        if matches!(
+            self.upscaler.format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        ) {
+            for pixel in pixels.chunks_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        image::RgbaImage::from_raw(width, height, pixels).unwrap()
+    }
+
+    pub fn viewport(&self) -> (u32, u32) {
+        self.upscaler.display.virtual_size
+    }
+
+    fn chunk_visible(&self, coords: (i64, i64), view: &View, parallax: (f32, f32)) -> bool {
+        let chunk_size = (CHUNK_SIZE * TILE_SIZE) as f32;
+        let (mut half_w, mut half_h) = view.half_extents(self.viewport());
+        if view.rotation != 0.0 {
+            half_w = half_w.hypot(half_h);
+            half_h = half_w;
+        }
+        let (half_w, half_h) = (half_w + TILE_SIZE as f32, half_h + TILE_SIZE as f32);
+        let (view_x, view_y) = (view.x * parallax.0, view.y * parallax.1);
+        let (x, y) = (coords.0 as f32 * chunk_size, coords.1 as f32 * chunk_size);
+        x < view_x + half_w
+            && x + chunk_size > view_x - half_w
+            && y < view_y + half_h
+            && y + chunk_size > view_y - half_h
+    }
+
+    fn build_chunk(&self, coords: (i64, i64), chunk: &TileChunk) -> CachedChunk {
+        let mut pages: Vec<Vec<super::sprite::Instance>> = vec![Vec::new(); self.atlas.len()];
+        for (tile, batch) in chunk.batch(coords) {
+            let region = match self.regions.tiles.get(&tile) {
+                Some(region) => region,
+                None => continue,
+            };
+            let animation = self.tile_animations.get(&tile);
+            let frames = animation.map_or(1, |animation| animation.frames) as usize;
+            pages[region.page].extend(batch.iter().map(|(version, x, y)| {
+                let uv = region.cell(*version, NUM_TILE_VERSIONS, frames);
+                super::sprite::Instance {
+                    uv,
+                    position: [
+                        ((*x * PIXEL_SIZE as i64 + PIXEL_SIZE as i64 / 2) * TILE_SIZE as i64)
+                            as f32,
+                        ((*y * PIXEL_SIZE as i64 + PIXEL_SIZE as i64 / 2) * TILE_SIZE as i64)
+                            as f32,
+                    ],
+                    size: [
+                        (region.size.0 as usize * PIXEL_SIZE) as f32 / NUM_TILE_VERSIONS as f32,
+                        (region.size.1 as usize * PIXEL_SIZE) as f32 / frames as f32,
+                    ],
+                    animation: [
+                        frames as f32,
+                        animation.map_or(1.0, |animation| animation.frame_duration),
+                        animation.map_or(0.0, |animation| animation.phase(*x, *y)),
+                    ],
+                    flip: [1.0, 1.0],
+                    pivot: [0.0, 0.0],
+                    rotation: 0.0,
+                    tint: [1.0; 4],
+                }
+            }));
+        }
+        let (instances, draws) = concat_pages(pages);
+        let instance_buffer = if instances.is_empty() {
+            None
+        } else {
+            Some(
+                self.device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Chunk Instance Buffer"),
+                        contents: bytemuck::cast_slice(instances.as_ref()),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    }),
+            )
+        };
+        CachedChunk {
+            instance_buffer,
+            draws,
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        sprites: &SpriteBatch,
+        tiles: &TileBatch,
+        text: &TextBatch,
+        view: View,
+        time: f32,
+    ) {
+        while self.cameras.len() < tiles.len() + 1 {
+            let camera = create_camera(&self.device, &self.camera_bind_group_layout);
+            self.cameras.push(camera);
+        }
+        self.queue.write_buffer(
+            &self.cameras[0].0,
+            0,
+            bytemuck::cast_slice(&view.uniform((1.0, 1.0), self.viewport(), time)),
+        );
+        self.queue.write_buffer(
+            &self.screen_camera.0,
+            0,
+            bytemuck::cast_slice(&View::default().uniform((1.0, 1.0), self.viewport(), time)),
+        );
+        let mut visible = Vec::new();
+        for (i, layer) in tiles.iter().enumerate() {
+            self.queue.write_buffer(
+                &self.cameras[i + 1].0,
+                0,
+                bytemuck::cast_slice(&view.uniform(layer.parallax, self.viewport(), time)),
+            );
+            let mut revisions = Vec::new();
+            for (coords, chunk) in layer.chunks.iter() {
+                if !self.chunk_visible(*coords, &view, layer.parallax) {
+                    continue;
+                }
+                if !self.chunk_cache.contains_key(&chunk.revision) {
+                    let cached = self.build_chunk(*coords, chunk);
+                    self.chunk_cache.insert(chunk.revision, cached);
+                }
+                revisions.push(chunk.revision);
+            }
+            visible.push(revisions);
+        }
+        let live: HashSet<u64> = tiles
+            .iter()
+            .flat_map(|layer| layer.chunks.iter().map(|(_, chunk)| chunk.revision))
+            .collect();
+        self.chunk_cache
+            .retain(|revision, _| live.contains(revision));
+
+        let (sprite_draws, screen_draws) = self.upload_sprites(sprites, text);
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view: &self.upscaler.target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: 0.0,
+                            g: 0.0,
+                            b: 0.0,
+                            a: 1.0,
+                        }),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_vertex_buffer(0, self.vertex_buffers.slice(..));
+
+            let mut layers: Vec<usize> = (0..tiles.len()).collect();
+            layers.sort_by_key(|i| tiles[*i].order);
+            let mut next_sprite = 0;
+            for i in layers {
+                let end = next_sprite
+                    + sprite_draws[next_sprite..]
+                        .iter()
+                        .take_while(|(layer, _, _)| *layer < tiles[i].order)
+                        .count();
+                self.draw_sprites(
+                    &mut render_pass,
+                    &self.cameras[0].1,
+                    &sprite_draws[next_sprite..end],
+                );
+                next_sprite = end;
+                self.draw_tile_layer(&mut render_pass, i + 1, &visible[i]);
+            }
+            self.draw_sprites(
+                &mut render_pass,
+                &self.cameras[0].1,
+                &sprite_draws[next_sprite..],
+            );
+            self.draw_sprites(&mut render_pass, &self.screen_camera.1, &screen_draws);
+        }
+    }
+
+    fn glyphs(&self, text: &Text, origin: (f32, f32)) -> Vec<super::sprite::Instance> {
+        let font = font();
+        let (w, h) = (
+            font.cell.0 as f32 * text.scale,
+            font.cell.1 as f32 * text.scale,
+        );
+        font.layout(text)
+            .into_iter()
+            .map(|glyph| super::sprite::Instance {
+                uv: self.regions.font.grid_cell(
+                    glyph.column as usize,
+                    glyph.row as usize,
+                    font.columns as usize,
+                    font.rows() as usize,
+                ),
+                position: [
+                    (origin.0 + glyph.x + w / 2.0) * PIXEL_SIZE as f32,
+                    (origin.1 - glyph.y - h / 2.0) * PIXEL_SIZE as f32,
+                ],
+                size: [w * PIXEL_SIZE as f32, h * PIXEL_SIZE as f32],
+                animation: [1.0, 1.0, 0.0],
+                flip: [1.0, 1.0],
+                pivot: [0.0, 0.0],
+                rotation: 0.0,
+                tint: text.color,
+            })
+            .collect()
+    }
+
+    fn upload_sprites(
+        &mut self,
+        sprites: &SpriteBatch,
+        text: &TextBatch,
+    ) -> (Vec<SpriteDraw>, Vec<SpriteDraw>) {
+        let mut world: Vec<(i32, f32, usize, super::sprite::Instance)> = Vec::new();
+        for (i, batch) in sprites.iter().enumerate() {
+            let region = &self.regions.sprites[i];
+            let frames = Sprite::ALL[i].frames();
+            for sprite in batch.iter() {
+                let [r, g, b, a] = sprite.tint;
+                let instance = super::sprite::Instance {
+                    uv: region.cell(sprite.frame, frames, 1),
+                    position: [sprite.x * PIXEL_SIZE as f32, sprite.y * PIXEL_SIZE as f32],
+                    size: [
+                        (region.size.0 as usize * PIXEL_SIZE) as f32 / frames as f32 * sprite.w,
+                        (region.size.1 as usize * PIXEL_SIZE) as f32 * sprite.h,
+                    ],
+                    animation: [1.0, 1.0, 0.0],
+                    flip: [
+                        if sprite.flip_x { -1.0 } else { 1.0 },
+                        if sprite.flip_y { -1.0 } else { 1.0 },
+                    ],
+                    pivot: [sprite.pivot.0, sprite.pivot.1],
+                    rotation: sprite.rotation,
+                    tint: [r, g, b, a * sprite.alpha],
+                };
+                world.push((sprite.layer, sprite.depth, region.page, instance));
+            }
+        }
+        let mut screen = Vec::new();
+        let (half_w, half_h) = (
+            self.viewport().0 as f32 / 2.0,
+            self.viewport().1 as f32 / 2.0,
+        );
+        for text in text.iter() {
+            let (target, origin) = match text.anchor {
+                Anchor::World => (&mut world, (text.x, text.y)),
+                Anchor::Screen => (&mut screen, (text.x - half_w, half_h - text.y)),
+            };
+            let page = self.regions.font.page;
+            target.extend(
+                self.glyphs(text, origin)
+                    .into_iter()
+                    .map(|instance| (text.layer, f32::NEG_INFINITY, page, instance)),
+            );
+        }
+        for items in [&mut world, &mut screen] {
+            items.sort_by(|a, b| {
+                a.0.cmp(&b.0)
+                    .then(b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal))
+            });
+        }
+
+        let mut instances = Vec::with_capacity(world.len() + screen.len());
+        let mut batches = Vec::new();
+        for items in [world, screen] {
+            let mut draws: Vec<SpriteDraw> = Vec::new();
+            for (layer, _, page, instance) in items {
+                let index = instances.len() as u32;
+                match draws.last_mut() {
+                    Some((last_layer, last_page, range))
+                        if *last_layer == layer && *last_page == page =>
+                    {
+                        range.end = index + 1
+                    }
+                    _ => draws.push((layer, page, index..index + 1)),
+                }
+                instances.push(instance);
+            }
+            batches.push(draws);
+        }
+        if instances.len() > self.instance_capacity {
+            self.instance_capacity = instances.len().next_power_of_two();
+            self.instance_buffer = create_instance_buffer(&self.device, self.instance_capacity);
+        }
+        self.queue.write_buffer(
+            &self.instance_buffer,
+            0,
+            bytemuck::cast_slice(instances.as_ref()),
+        );
+        let screen = batches.pop().unwrap();
+        (batches.pop().unwrap(), screen)
+    }
+
+    fn draw_sprites<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        camera: &'a wgpu::BindGroup,
+        draws: &[SpriteDraw],
+    ) {
+        if draws.is_empty() {
+            return;
+        }
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        render_pass.set_bind_group(1, camera, &[]);
+        for (_, page, range) in draws.iter() {
+            render_pass.set_bind_group(0, &self.atlas[*page].1, &[]);
+            render_pass.draw(0..4, range.clone());
+        }
+    }
+
+    fn draw_tile_layer<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        camera: usize,
+        revisions: &[u64],
+    ) {
+        render_pass.set_bind_group(1, &self.cameras[camera].1, &[]);
+        for revision in revisions.iter() {
+            let chunk = &self.chunk_cache[revision];
+            let instance_buffer = match &chunk.instance_buffer {
+                Some(x) => x,
+                None => continue,
+            };
+            render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+            for (page, range) in chunk.draws.iter() {
+                render_pass.set_bind_group(0, &self.atlas[*page].1, &[]);
+                render_pass.draw(0..4, range.clone());
+            }
+        }
+    }
+}
+
+fn create_camera(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+) -> (wgpu::Buffer, wgpu::BindGroup) {
+    let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Camera buffer"),
+        contents: bytemuck::cast_slice(&[0.0f32; 8]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: camera_buffer.as_entire_binding(),
+        }],
+        label: Some("camera_bind_group"),
+    });
+    (camera_buffer, camera_bind_group)
+}
+
+fn concat_pages(
+    pages: Vec<Vec<super::sprite::Instance>>,
+) -> (Vec<super::sprite::Instance>, Vec<(usize, Range<u32>)>) {
+    let mut instances = Vec::new();
+    let mut draws = Vec::new();
+    for (page, page_instances) in pages.into_iter().enumerate() {
+        if !page_instances.is_empty() {
+            let start = instances.len() as u32;
+            instances.extend(page_instances);
+            draws.push((page, start..instances.len() as u32));
+        }
+    }
+    (instances, draws)
+}
+
+fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Instance Buffer"),
+        size: (capacity * std::mem::size_of::<super::sprite::Instance>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn load_images(
+    assets: &mut AssetServer,
+    tile_images: &[(Tile, String)],
+) -> std::io::Result<Vec<(image::RgbaImage, usize)>> {
+    let sprites =
+        Sprite::ALL.map(|sprite| (sprite_sheets().get(sprite).image.as_str(), sprite.frames()));
+    let tiles = tile_images
+        .iter()
+        .map(|(_, image)| (image.as_str(), NUM_TILE_VERSIONS));
+    sprites
+        .into_iter()
+        .chain(std::iter::once((
+            font().image.as_str(),
+            font().columns as usize,
+        )))
+        .chain(tiles)
+        .map(|(path, columns)| {
+            let data = assets.load(path)?;
+            let img = image::load_from_memory(&data).map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Couldn't load {}: {}.", path, e),
+                )
+            })?;
+            Ok((img.to_rgba8(), columns))
+        })
+        .collect()
+}
+
+fn build_atlas(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    images: &[(image::RgbaImage, usize)],
+    tile_images: &[(Tile, String)],
+) -> std::io::Result<(AtlasPages, Regions, wgpu::BindGroupLayout)> {
+    let (pages, regions) = pack_atlas(images, device.limits().max_texture_dimension_2d)?;
+    let mut texture_bind_group_layout = None;
+    let atlas = pages
+        .into_iter()
+        .map(|page| {
+            let texture = super::sprite::Texture::from_image(
+                device,
+                queue,
+                &image::DynamicImage::ImageRgba8(page),
+                Some("Atlas"),
+            )
+            .unwrap();
+            let (bind_group, layout) = create_texture_bind_group(&[&texture], device);
+            texture_bind_group_layout = Some(layout);
+            (texture, bind_group)
+        })
+        .collect();
+    let regions = Regions {
+        sprites: regions[..NUM_SPRITES].to_vec(),
+        font: regions[NUM_SPRITES],
+        tiles: tile_images
+            .iter()
+            .zip(regions[NUM_SPRITES + 1..].iter())
+            .map(|((tile, _), region)| (*tile, *region))
+            .collect(),
+    };
+    Ok((atlas, regions, texture_bind_group_layout.unwrap()))
+}
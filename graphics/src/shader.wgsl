@@ -13,8 +13,12 @@
 // 
 
 struct Camera {
-    x: f32;
-    y: f32;
+    position: vec2<f32>;
+    viewport: vec2<f32>;
+    zoom: f32;
+    rotation: f32;
+    time: f32;
+    pixel: f32;
 };
 [[group(1), binding(0)]]
 var<uniform> camera: Camera;
@@ -25,19 +29,20 @@ struct VertexInput {
 };
 
 struct InstanceInput {
-    [[location(2)]] texoffset: f32;
-    [[location(3)]] texwidth: f32;
-    [[location(4)]] x: f32;
-    [[location(5)]] y: f32;
-    [[location(6)]] w: f32;
-    [[location(7)]] h: f32;
-    [[location(8)]] ww: f32;
-    [[location(9)]] wh: f32;
+    [[location(2)]] uv: vec4<f32>;
+    [[location(3)]] position: vec2<f32>;
+    [[location(4)]] size: vec2<f32>;
+    [[location(5)]] animation: vec3<f32>;
+    [[location(6)]] flip: vec2<f32>;
+    [[location(7)]] pivot: vec2<f32>;
+    [[location(8)]] rotation: f32;
+    [[location(9)]] tint: vec4<f32>;
 };
 
 struct VertexOutput {
     [[builtin(position)]] clip_position: vec4<f32>;
     [[location(0)]] texcoord: vec2<f32>;
+    [[location(1)]] tint: vec4<f32>;
 };
 
 [[stage(vertex)]]
@@ -46,8 +51,22 @@ fn vs_main(
     instance: InstanceInput,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.texcoord = vec2<f32>(model.texcoord.x * instance.texwidth + instance.texoffset, model.texcoord.y);
-    out.clip_position = vec4<f32>(model.position.x * instance.w / instance.ww + instance.x / instance.ww - camera.x / instance.ww, model.position.y * instance.h / instance.wh + instance.y / instance.wh - camera.y / instance.wh, 0.0, 1.0);
+    let frame = floor((camera.time + instance.animation.z) / instance.animation.y) % instance.animation.x;
+    let texcoord = (model.texcoord - 0.5) * instance.flip + 0.5;
+    out.texcoord = vec2<f32>(instance.uv.x + texcoord.x * instance.uv.z, instance.uv.y + (texcoord.y + frame) * instance.uv.w);
+    let local = (model.position - instance.pivot) * instance.size;
+    let c = cos(instance.rotation);
+    let s = sin(instance.rotation);
+    let rotated = vec2<f32>(local.x * c - local.y * s, local.x * s + local.y * c) + instance.pivot * instance.size;
+    let scale = camera.zoom / camera.pixel;
+    let half = instance.size * scale * 0.5;
+    let origin = round((instance.position - camera.position) * scale - half) + half;
+    let relative = origin + rotated * scale;
+    let cc = cos(-camera.rotation);
+    let cs = sin(-camera.rotation);
+    let view = vec2<f32>(relative.x * cc - relative.y * cs, relative.x * cs + relative.y * cc);
+    out.clip_position = vec4<f32>(view / camera.viewport, 0.0, 1.0);
+    out.tint = instance.tint;
     return out;
 }
 
@@ -58,5 +77,5 @@ var frag_sampler: sampler;
 
 [[stage(fragment)]]
 fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
-    return textureSample(frag_texture, frag_sampler, in.texcoord);
+    return textureSample(frag_texture, frag_sampler, in.texcoord) * in.tint;
 }
@@ -17,37 +17,93 @@ use wgpu::{BindGroup, BindGroupLayout, Device};
 use image::GenericImageView;
 use image::ImageResult;
 
+use serde::{Deserialize, Serialize};
+
+use super::animation::*;
+
 pub const NUM_SPRITES: usize = 2;
 
 #[repr(usize)]
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Deserialize, Serialize)]
 pub enum Sprite {
     TestSprite1,
     TestSprite2,
 }
 
 impl Sprite {
-    pub fn frames(s: usize) -> usize {
-        match unsafe { std::mem::transmute(s) } {
-            Sprite::TestSprite1 => 2,
-            Sprite::TestSprite2 => 1,
-        }
+    pub const ALL: [Sprite; NUM_SPRITES] = [Sprite::TestSprite1, Sprite::TestSprite2];
+
+    pub fn frames(self) -> usize {
+        sprite_sheets().get(self).frames
     }
 }
 
-pub type SpriteBatch = [Vec<(usize, f32, f32, f32, f32)>; NUM_SPRITES];
+pub const SPRITE_LAYER: i32 = 0;
+pub const UI_LAYER: i32 = 1000;
 
-#[repr(C)]
-#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct Instance {
-    pub texoffset: f32,
-    pub texwidth: f32,
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpriteInstance {
+    pub frame: usize,
     pub x: f32,
     pub y: f32,
     pub w: f32,
     pub h: f32,
-    pub ww: f32,
-    pub wh: f32,
+    pub layer: i32,
+    pub depth: f32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub rotation: f32,
+    pub pivot: (f32, f32),
+    pub tint: [f32; 4],
+    pub alpha: f32,
+}
+
+impl SpriteInstance {
+    pub fn new(frame: usize, x: f32, y: f32) -> Self {
+        SpriteInstance {
+            frame,
+            x,
+            y,
+            w: 1.0,
+            h: 1.0,
+            layer: SPRITE_LAYER,
+            depth: 0.0,
+            flip_x: false,
+            flip_y: false,
+            rotation: 0.0,
+            pivot: (0.0, 0.0),
+            tint: [1.0; 4],
+            alpha: 1.0,
+        }
+    }
+}
+
+pub type SpriteBatch = [Vec<SpriteInstance>; NUM_SPRITES];
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Instance {
+    pub uv: [f32; 4],
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    pub animation: [f32; 3],
+    pub flip: [f32; 2],
+    pub pivot: [f32; 2],
+    pub rotation: f32,
+    pub tint: [f32; 4],
+}
+
+impl Instance {
+    pub const ATTRIBUTES: [wgpu::VertexAttribute; 8] = wgpu::vertex_attr_array![
+        2 => Float32x4,
+        3 => Float32x2,
+        4 => Float32x2,
+        5 => Float32x3,
+        6 => Float32x2,
+        7 => Float32x2,
+        8 => Float32,
+        9 => Float32x4,
+    ];
 }
 
 #[repr(C)]
@@ -201,20 +257,3 @@ pub fn create_texture_bind_group(
     });
     (texture_bind_group, texture_bind_group_layout)
 }
-
-macro_rules! create_textures {
-    ($a:expr, $b:expr, $($x:literal),+) => {
-        {
-            let mut textures_vec: Vec<super::sprite::Texture> = vec![];
-            let mut bind_groups_vec: Vec<BindGroup> = vec![];
-            let mut bind_group_layouts_vec: Vec<BindGroupLayout> = vec![];
-            $(
-                textures_vec.push(super::sprite::Texture::from_bytes($a, $b, include_bytes!($x), None).unwrap());
-                let (group, layout) = create_texture_bind_group(&[textures_vec.last().unwrap()], $a);
-                bind_groups_vec.push(group);
-                bind_group_layouts_vec.push(layout);
-            )*
-                (textures_vec, bind_groups_vec, bind_group_layouts_vec)
-        }
-    }
-}
@@ -0,0 +1,173 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::io::{Error, ErrorKind};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use super::sprite::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Anchor {
+    #[default]
+    World,
+    Screen,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Text {
+    pub text: String,
+    pub x: f32,
+    pub y: f32,
+    pub anchor: Anchor,
+    pub align: Align,
+    pub wrap: Option<f32>,
+    pub scale: f32,
+    pub color: [f32; 4],
+    pub layer: i32,
+}
+
+impl Text {
+    pub fn new(text: &str, x: f32, y: f32) -> Self {
+        Text {
+            text: String::from(text),
+            x,
+            y,
+            anchor: Anchor::World,
+            align: Align::Left,
+            wrap: None,
+            scale: 1.0,
+            color: [1.0; 4],
+            layer: UI_LAYER,
+        }
+    }
+
+    pub fn screen(text: &str, x: f32, y: f32) -> Self {
+        Text {
+            anchor: Anchor::Screen,
+            ..Text::new(text, x, y)
+        }
+    }
+}
+
+pub type TextBatch = Vec<Text>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Glyph {
+    pub column: u32,
+    pub row: u32,
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Font {
+    pub image: String,
+    pub cell: (u32, u32),
+    pub columns: u32,
+    pub first: char,
+    pub count: u32,
+    pub line_height: u32,
+}
+
+impl Font {
+    pub fn parse(text: &str) -> std::io::Result<Self> {
+        let font: Font = ron::from_str(text).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        if font.columns == 0 || font.count == 0 || font.cell.0 == 0 || font.cell.1 == 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "Font has no glyphs."));
+        }
+        Ok(font)
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.count.div_ceil(self.columns)
+    }
+
+    pub fn glyph(&self, c: char) -> Option<(u32, u32)> {
+        let index = (c as u32).checked_sub(self.first as u32)?;
+        if index < self.count {
+            Some((index % self.columns, index / self.columns))
+        } else {
+            None
+        }
+    }
+
+    pub fn lines(&self, text: &str, wrap: Option<f32>) -> Vec<String> {
+        let max = wrap.map_or(usize::MAX, |wrap| {
+            ((wrap / self.cell.0 as f32).floor() as usize).max(1)
+        });
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let mut line: Vec<char> = Vec::new();
+            for (i, word) in paragraph.split(' ').enumerate() {
+                let word: Vec<char> = word.chars().collect();
+                if i > 0 && !line.is_empty() {
+                    if line.len() + 1 + word.len() > max {
+                        lines.push(std::mem::take(&mut line));
+                    } else {
+                        line.push(' ');
+                    }
+                }
+                for c in word {
+                    if line.len() == max {
+                        lines.push(std::mem::take(&mut line));
+                    }
+                    line.push(c);
+                }
+            }
+            lines.push(line);
+        }
+        lines.into_iter().map(String::from_iter).collect()
+    }
+
+    pub fn layout(&self, text: &Text) -> Vec<Glyph> {
+        let wrap = text.wrap.map(|wrap| wrap / text.scale);
+        let mut glyphs = Vec::new();
+        for (i, line) in self.lines(&text.text, wrap).iter().enumerate() {
+            let width = (line.trim_end().chars().count() as u32 * self.cell.0) as f32;
+            let offset = match text.align {
+                Align::Left => 0.0,
+                Align::Center => -(width / 2.0).floor(),
+                Align::Right => -width,
+            };
+            for (j, c) in line.chars().enumerate() {
+                if c == ' ' {
+                    continue;
+                }
+                let (column, row) = self.glyph(c).or_else(|| self.glyph('?')).unwrap_or((0, 0));
+                glyphs.push(Glyph {
+                    column,
+                    row,
+                    x: (offset + (j as u32 * self.cell.0) as f32) * text.scale,
+                    y: (i as u32 * self.line_height) as f32 * text.scale,
+                });
+            }
+        }
+        glyphs
+    }
+}
+
+pub fn font() -> &'static Font {
+    static FONT: OnceLock<Font> = OnceLock::new();
+    FONT.get_or_init(|| Font::parse(include_str!("../../assets/font.ron")).unwrap())
+}
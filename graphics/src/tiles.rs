@@ -13,47 +13,103 @@
  */
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
-pub const NUM_TILES: usize = 2;
+use super::autotile::*;
+use super::edit::*;
+use super::registry::*;
+
 pub const NUM_TILE_VERSIONS: usize = 256;
 pub const TILE_SIZE: usize = 16;
 pub const CHUNK_SIZE: usize = 16;
 pub const PIXEL_SIZE: usize = 4;
 
-#[repr(usize)]
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Deserialize, Serialize)]
-pub enum Tile {
-    TestTile1,
-    TestTile2,
-    NoTile,
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct Tile(pub u32);
+
+impl Tile {
+    pub const NO_TILE: Tile = Tile(0);
+}
+
+pub type Chunk = [[(Tile, usize); CHUNK_SIZE]; CHUNK_SIZE];
+
+pub fn chunk_is_empty(chunk: &Chunk) -> bool {
+    chunk
+        .iter()
+        .all(|row| row.iter().all(|(tile, _)| *tile == Tile::NO_TILE))
+}
+
+static REVISION: AtomicU64 = AtomicU64::new(1);
+
+fn next_revision() -> u64 {
+    REVISION.fetch_add(1, Ordering::Relaxed)
 }
 
-impl Default for Tile {
+#[derive(Clone, Deserialize, Serialize)]
+pub struct TileChunk {
+    pub tiles: Chunk,
+    #[serde(skip, default = "next_revision")]
+    pub revision: u64,
+}
+
+impl Default for TileChunk {
     fn default() -> Self {
-        Tile::NoTile
+        TileChunk {
+            tiles: Default::default(),
+            revision: next_revision(),
+        }
     }
 }
 
 #[derive(Default, Deserialize, Serialize)]
-pub struct Tiles(pub HashMap<(i64, i64), [[(Tile, usize); CHUNK_SIZE]; CHUNK_SIZE]>);
+pub struct TileLayer {
+    pub name: String,
+    pub order: i32,
+    pub parallax: (f32, f32),
+    pub collision: bool,
+    chunks: HashMap<(i64, i64), Arc<TileChunk>>,
+}
 
-impl Tiles {
-    pub fn get(&mut self, x: i64, y: i64) -> &(Tile, usize) {
-        let c_x = x.div_euclid(CHUNK_SIZE as i64);
-        let c_y = y.div_euclid(CHUNK_SIZE as i64);
-        let t_x = x.rem_euclid(CHUNK_SIZE as i64);
-        let t_y = y.rem_euclid(CHUNK_SIZE as i64);
-        &self.0.entry((c_x, c_y)).or_insert(Default::default())[t_x as usize][t_y as usize]
+impl TileLayer {
+    pub fn new(name: &str, order: i32, parallax: (f32, f32), collision: bool) -> Self {
+        TileLayer {
+            name: String::from(name),
+            order,
+            parallax,
+            collision,
+            chunks: HashMap::new(),
+        }
     }
 
-    pub fn get_mut(&mut self, x: i64, y: i64) -> &mut (Tile, usize) {
+    pub fn get(&self, x: i64, y: i64) -> (Tile, usize) {
+        self.at(x, y).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, tile: (Tile, usize)) {
         let c_x = x.div_euclid(CHUNK_SIZE as i64);
         let c_y = y.div_euclid(CHUNK_SIZE as i64);
         let t_x = x.rem_euclid(CHUNK_SIZE as i64);
         let t_y = y.rem_euclid(CHUNK_SIZE as i64);
-        &mut self.0.entry((c_x, c_y)).or_insert(Default::default())[t_x as usize][t_y as usize]
+        let chunk = if tile.0 == Tile::NO_TILE {
+            match self.chunks.get_mut(&(c_x, c_y)) {
+                Some(chunk) => chunk,
+                None => return,
+            }
+        } else {
+            self.chunks.entry((c_x, c_y)).or_default()
+        };
+        if chunk.tiles[t_x as usize][t_y as usize] == tile {
+            return;
+        }
+        let chunk = Arc::make_mut(chunk);
+        chunk.tiles[t_x as usize][t_y as usize] = tile;
+        chunk.revision = next_revision();
+        if chunk_is_empty(&chunk.tiles) {
+            self.chunks.remove(&(c_x, c_y));
+        }
     }
 
     pub fn at(&self, x: i64, y: i64) -> Option<&(Tile, usize)> {
@@ -61,16 +117,184 @@ impl Tiles {
         let c_y = y.div_euclid(CHUNK_SIZE as i64);
         let t_x = x.rem_euclid(CHUNK_SIZE as i64);
         let t_y = y.rem_euclid(CHUNK_SIZE as i64);
-        Some(&self.0.get(&(c_x, c_y))?[t_x as usize][t_y as usize])
+        Some(&self.chunks.get(&(c_x, c_y))?.tiles[t_x as usize][t_y as usize])
     }
 
-    pub fn at_mut(&mut self, x: i64, y: i64) -> Option<&mut (Tile, usize)> {
-        let c_x = x.div_euclid(CHUNK_SIZE as i64);
-        let c_y = y.div_euclid(CHUNK_SIZE as i64);
-        let t_x = x.rem_euclid(CHUNK_SIZE as i64);
-        let t_y = y.rem_euclid(CHUNK_SIZE as i64);
-        Some(&mut self.0.get_mut(&(c_x, c_y))?[t_x as usize][t_y as usize])
+    pub fn insert_chunk(&mut self, coords: (i64, i64), tiles: Chunk) {
+        self.chunks.insert(
+            coords,
+            Arc::new(TileChunk {
+                tiles,
+                revision: next_revision(),
+            }),
+        );
+    }
+
+    pub fn remove_chunk(&mut self, coords: (i64, i64)) -> Option<Chunk> {
+        self.chunks.remove(&coords).map(|chunk| chunk.tiles)
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = (&(i64, i64), &TileChunk)> {
+        self.chunks
+            .iter()
+            .map(|(coords, chunk)| (coords, chunk.as_ref()))
+    }
+
+    pub fn compact(&mut self) {
+        self.chunks.retain(|_, chunk| !chunk_is_empty(&chunk.tiles));
+    }
+
+    pub fn bounds(&self) -> Option<(i64, i64, i64, i64)> {
+        let mut bounds: Option<(i64, i64, i64, i64)> = None;
+        for (coords, chunk) in self.chunks.iter() {
+            for (r, row) in chunk.tiles.iter().enumerate() {
+                for (c, (tile, _)) in row.iter().enumerate() {
+                    if *tile != Tile::NO_TILE {
+                        let x = coords.0 * CHUNK_SIZE as i64 + r as i64;
+                        let y = coords.1 * CHUNK_SIZE as i64 + c as i64;
+                        bounds = Some(match bounds {
+                            None => (x, y, x, y),
+                            Some((min_x, min_y, max_x, max_y)) => {
+                                (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+                            }
+                        });
+                    }
+                }
+            }
+        }
+        bounds
+    }
+
+    pub fn batch(&self) -> TileLayerBatch {
+        TileLayerBatch {
+            order: self.order,
+            parallax: self.parallax,
+            chunks: self
+                .chunks
+                .iter()
+                .map(|(coords, chunk)| (*coords, chunk.clone()))
+                .collect(),
+        }
+    }
+}
+
+pub const BACKGROUND_LAYER: &str = "background";
+pub const MAIN_LAYER: &str = "main";
+pub const FOREGROUND_LAYER: &str = "foreground";
+
+#[derive(Deserialize, Serialize)]
+pub struct Tiles {
+    pub layers: Vec<TileLayer>,
+    #[serde(skip)]
+    pub terrains: Terrains,
+    #[serde(skip)]
+    pub properties: HashMap<Tile, TileProperties>,
+    #[serde(skip)]
+    pub(crate) pending_changes: Vec<TileChanged>,
+    #[serde(skip)]
+    pub(crate) changes: Vec<TileChanged>,
+    #[serde(skip)]
+    pub(crate) recording: Option<Vec<TileEdit>>,
+}
+
+impl Default for Tiles {
+    fn default() -> Self {
+        Tiles {
+            layers: vec![
+                TileLayer::new(BACKGROUND_LAYER, -2, (1.0, 1.0), false),
+                TileLayer::new(MAIN_LAYER, -1, (1.0, 1.0), true),
+                TileLayer::new(FOREGROUND_LAYER, 1, (1.0, 1.0), false),
+            ],
+            terrains: Default::default(),
+            properties: Default::default(),
+            pending_changes: Vec::new(),
+            changes: Vec::new(),
+            recording: None,
+        }
+    }
+}
+
+impl Tiles {
+    pub fn layer(&self, name: &str) -> Option<&TileLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut TileLayer> {
+        self.layers.iter_mut().find(|layer| layer.name == name)
+    }
+
+    pub fn add_layer(&mut self, layer: TileLayer) {
+        let index = self
+            .layers
+            .iter()
+            .position(|other| other.order > layer.order)
+            .unwrap_or(self.layers.len());
+        self.layers.insert(index, layer);
+    }
+
+    pub fn properties(&self, tile: Tile) -> Option<&TileProperties> {
+        self.properties.get(&tile)
+    }
+
+    pub fn is_solid(&self, tile: Tile) -> bool {
+        tile != Tile::NO_TILE
+            && self
+                .properties(tile)
+                .is_none_or(|properties| properties.solid)
+    }
+
+    pub fn collision_at(&self, x: i64, y: i64) -> Option<Tile> {
+        let mut found = None;
+        for layer in self.layers.iter().filter(|layer| layer.collision) {
+            match layer.at(x, y) {
+                Some((tile, _)) if self.is_solid(*tile) => return Some(*tile),
+                Some(_) => found = Some(Tile::NO_TILE),
+                None => {}
+            }
+        }
+        found
+    }
+
+    pub fn compact(&mut self) {
+        for layer in self.layers.iter_mut() {
+            layer.compact();
+        }
+    }
+
+    pub fn bounds(&self) -> Option<(i64, i64, i64, i64)> {
+        self.layers
+            .iter()
+            .filter_map(|layer| layer.bounds())
+            .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+    }
+
+    pub fn batch(&self) -> TileBatch {
+        self.layers.iter().map(|layer| layer.batch()).collect()
+    }
+}
+
+pub struct TileLayerBatch {
+    pub order: i32,
+    pub parallax: (f32, f32),
+    pub chunks: Vec<((i64, i64), Arc<TileChunk>)>,
+}
+
+impl TileChunk {
+    pub fn batch(&self, coords: (i64, i64)) -> HashMap<Tile, Vec<(usize, i64, i64)>> {
+        let mut batch: HashMap<Tile, Vec<(usize, i64, i64)>> = HashMap::new();
+        for (r, row) in self.tiles.iter().enumerate() {
+            for (c, (tile, version)) in row.iter().enumerate() {
+                if *tile != Tile::NO_TILE {
+                    batch.entry(*tile).or_default().push((
+                        *version,
+                        coords.0 * CHUNK_SIZE as i64 + r as i64,
+                        coords.1 * CHUNK_SIZE as i64 + c as i64,
+                    ));
+                }
+            }
+        }
+        batch
     }
 }
 
-pub type TileBatch = [Vec<(usize, i64, i64)>; NUM_TILES];
+pub type TileBatch = Vec<TileLayerBatch>;
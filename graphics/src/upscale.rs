@@ -0,0 +1,230 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use wgpu::util::DeviceExt;
+use wgpu::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Upscale {
+    #[default]
+    Integer,
+    SharpBilinear,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Display {
+    pub virtual_size: (u32, u32),
+    pub upscale: Upscale,
+}
+
+impl Default for Display {
+    fn default() -> Self {
+        Display {
+            virtual_size: (320, 180),
+            upscale: Upscale::Integer,
+        }
+    }
+}
+
+impl Display {
+    pub fn letterbox(&self, window: (u32, u32)) -> (f32, f32, f32, f32, f32) {
+        let (vw, vh) = (self.virtual_size.0 as f32, self.virtual_size.1 as f32);
+        let fit = (window.0 as f32 / vw).min(window.1 as f32 / vh);
+        let scale = match self.upscale {
+            Upscale::Integer if fit >= 1.0 => fit.floor(),
+            _ => fit,
+        };
+        let (w, h) = (
+            (vw * scale).min(window.0 as f32),
+            (vh * scale).min(window.1 as f32),
+        );
+        (
+            ((window.0 as f32 - w) / 2.0).floor(),
+            ((window.1 as f32 - h) / 2.0).floor(),
+            w,
+            h,
+            scale,
+        )
+    }
+}
+
+pub struct Upscaler {
+    pub display: Display,
+    pub format: wgpu::TextureFormat,
+    pub texture: wgpu::Texture,
+    pub target: wgpu::TextureView,
+    params: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl Upscaler {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, display: Display) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Virtual Target"),
+            size: wgpu::Extent3d {
+                width: display.virtual_size.0,
+                height: display.virtual_size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+        });
+        let target = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Upscale Params"),
+            contents: bytemuck::cast_slice(&[0.0f32; 4]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("upscale_bind_group_layout"),
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&target),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: params.as_entire_binding(),
+                },
+            ],
+            label: Some("upscale_bind_group"),
+        });
+
+        let shader = device.create_shader_module(&include_wgsl!("blit.wgsl"));
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Upscale Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Upscale Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Upscaler {
+            display,
+            format,
+            texture,
+            target,
+            params,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    pub fn draw(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+        window: (u32, u32),
+    ) {
+        let (x, y, w, h, scale) = self.display.letterbox(window);
+        queue.write_buffer(
+            &self.params,
+            0,
+            bytemuck::cast_slice(&[
+                self.display.virtual_size.0 as f32,
+                self.display.virtual_size.1 as f32,
+                scale,
+                (self.display.upscale == Upscale::SharpBilinear) as u32 as f32,
+            ]),
+        );
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Upscale Pass"),
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_viewport(x, y, w, h, 0.0, 1.0);
+        render_pass.draw(0..3, 0..1);
+    }
+}
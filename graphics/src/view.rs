@@ -0,0 +1,74 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::tiles::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct View {
+    pub x: f32,
+    pub y: f32,
+    pub zoom: f32,
+    pub rotation: f32,
+}
+
+impl View {
+    pub fn new(x: f32, y: f32) -> Self {
+        View {
+            x,
+            y,
+            zoom: 1.0,
+            rotation: 0.0,
+        }
+    }
+
+    pub fn half_extents(&self, viewport: (u32, u32)) -> (f32, f32) {
+        (
+            viewport.0 as f32 / 2.0 / self.zoom,
+            viewport.1 as f32 / 2.0 / self.zoom,
+        )
+    }
+
+    pub fn screen_to_world(&self, sx: f32, sy: f32) -> (f32, f32) {
+        let (x, y) = (sx / self.zoom, -sy / self.zoom);
+        let (s, c) = self.rotation.sin_cos();
+        (self.x + x * c - y * s, self.y + x * s + y * c)
+    }
+
+    pub fn snapped(&self, parallax: (f32, f32)) -> (f32, f32) {
+        (
+            (self.x * parallax.0 * self.zoom).round() / self.zoom,
+            (self.y * parallax.1 * self.zoom).round() / self.zoom,
+        )
+    }
+
+    pub fn uniform(&self, parallax: (f32, f32), viewport: (u32, u32), time: f32) -> [f32; 8] {
+        let (x, y) = self.snapped(parallax);
+        [
+            x * PIXEL_SIZE as f32,
+            y * PIXEL_SIZE as f32,
+            viewport.0 as f32 / 2.0,
+            viewport.1 as f32 / 2.0,
+            self.zoom,
+            self.rotation,
+            time,
+            PIXEL_SIZE as f32,
+        ]
+    }
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View::new(0.0, 0.0)
+    }
+}
@@ -0,0 +1,109 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use graphics::*;
+use image::{Rgba, RgbaImage};
+
+fn solid(w: u32, h: u32, shade: u8) -> (RgbaImage, usize) {
+    (RgbaImage::from_pixel(w, h, Rgba([shade, 0, 0, 255])), 1)
+}
+
+// A horizontal strip of `columns` cells, each filled with its own shade.
+fn strip(columns: u32, cell: (u32, u32)) -> (RgbaImage, usize) {
+    let image = RgbaImage::from_fn(columns * cell.0, cell.1, |x, _| {
+        Rgba([(x / cell.0 + 1) as u8, 0, 0, 255])
+    });
+    (image, columns as usize)
+}
+
+// The top-left pixel and size of `uv` on `page`.
+fn pixels(page: &RgbaImage, uv: [f32; 4]) -> (u32, u32, u32, u32) {
+    let (w, h) = (page.width() as f32, page.height() as f32);
+    (
+        (uv[0] * w).round() as u32,
+        (uv[1] * h).round() as u32,
+        (uv[2] * w).round() as u32,
+        (uv[3] * h).round() as u32,
+    )
+}
+
+#[test]
+fn fills_shelves_tallest_first() {
+    let images = [solid(20, 10, 1), solid(20, 8, 2), solid(30, 10, 3)];
+    let (pages, regions) = pack_atlas(&images, 64).unwrap();
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0].dimensions(), (64, 20));
+
+    let at: Vec<_> = regions
+        .iter()
+        .map(|region| pixels(&pages[0], region.uv))
+        .collect();
+    assert_eq!(at, [(0, 0, 20, 10), (0, 11, 20, 8), (21, 0, 30, 10)]);
+    for (region, (image, _)) in regions.iter().zip(images.iter()) {
+        let (x, y, ..) = pixels(&pages[0], region.uv);
+        assert_eq!(pages[0].get_pixel(x, y), image.get_pixel(0, 0));
+        assert_eq!(region.size, image.dimensions());
+    }
+}
+
+#[test]
+fn overflows_onto_new_pages() {
+    let images = [solid(16, 10, 1), solid(16, 10, 2), solid(16, 10, 3)];
+    let (pages, regions) = pack_atlas(&images, 16).unwrap();
+    assert_eq!(pages.len(), 3);
+    let mut used: Vec<_> = regions.iter().map(|region| region.page).collect();
+    used.sort_unstable();
+    assert_eq!(used, [0, 1, 2]);
+    assert!(pages.iter().all(|page| page.height() <= 16));
+}
+
+#[test]
+fn splits_wide_strips_into_bands() {
+    let images = [strip(8, (8, 2))];
+    let (pages, regions) = pack_atlas(&images, 32).unwrap();
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0].dimensions(), (32, 6));
+    assert_eq!(regions[0].band_columns, 4);
+    assert_eq!(regions[0].size, (64, 2));
+    for column in 0..8 {
+        let (x, y, w, h) = pixels(&pages[0], regions[0].cell(column, 8, 1));
+        assert_eq!((w, h), (8, 2));
+        for (dx, dy) in [(0, 0), (w - 1, h - 1)] {
+            assert_eq!(
+                pages[0].get_pixel(x + dx, y + dy),
+                &Rgba([column as u8 + 1, 0, 0, 255]),
+                "column {}",
+                column
+            );
+        }
+    }
+}
+
+#[test]
+fn fits_a_tileset_strip_under_small_limits() {
+    let images = [strip(NUM_TILE_VERSIONS as u32, (16, 16))];
+    let (pages, regions) = pack_atlas(&images, 2048).unwrap();
+    assert_eq!(pages[0].width(), 2048);
+    let (x, y, ..) = pixels(&pages[0], regions[0].cell(200, NUM_TILE_VERSIONS, 1));
+    assert_eq!(pages[0].get_pixel(x, y), &Rgba([201, 0, 0, 255]));
+}
+
+#[test]
+fn rejects_images_that_cannot_fit() {
+    assert!(pack_atlas(&[solid(20, 20, 1)], 16).is_err());
+    assert!(pack_atlas(&[strip(1, (32, 2))], 16).is_err());
+    assert!(pack_atlas(&[strip(8, (8, 10))], 16).is_err());
+    let (image, _) = strip(3, (10, 2));
+    assert!(pack_atlas(&[(image, 4)], 16).is_err());
+}
@@ -0,0 +1,137 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use graphics::*;
+
+const GROUND: Tile = Tile(1);
+const STONE: Tile = Tile(2);
+
+fn cells(changes: &[TileChanged]) -> Vec<(i64, i64)> {
+    let mut cells: Vec<_> = changes.iter().map(|change| (change.x, change.y)).collect();
+    cells.sort();
+    cells
+}
+
+#[test]
+fn changes_are_published_on_the_next_frame() {
+    let mut tiles = Tiles::default();
+    tiles.set_tile(MAIN_LAYER, 0, 0, GROUND);
+    assert!(tiles.changes().is_empty());
+
+    tiles.publish_changes();
+    assert_eq!(
+        tiles.changes(),
+        [TileChanged {
+            layer: String::from(MAIN_LAYER),
+            x: 0,
+            y: 0,
+            old: (Tile::NO_TILE, 0),
+            new: (GROUND, 0),
+        }]
+    );
+
+    tiles.publish_changes();
+    assert!(tiles.changes().is_empty());
+}
+
+#[test]
+fn set_tile_reports_autotiled_neighbours() {
+    let mut tiles = Tiles::default();
+    tiles.set_tile(MAIN_LAYER, 0, 0, GROUND);
+    tiles.publish_changes();
+    tiles.set_tile(MAIN_LAYER, 1, 0, GROUND);
+    tiles.publish_changes();
+    assert_eq!(cells(tiles.changes()), [(0, 0), (1, 0)]);
+    let left = tiles.changes().iter().find(|change| change.x == 0).unwrap();
+    assert_eq!(left.old, (GROUND, 0));
+    assert_eq!(left.new, (GROUND, 1 << 4));
+}
+
+#[test]
+fn unchanged_cells_are_not_reported() {
+    let mut tiles = Tiles::default();
+    tiles.set_tile(MAIN_LAYER, 0, 0, GROUND);
+    tiles.publish_changes();
+    tiles.set_tile(MAIN_LAYER, 0, 0, GROUND);
+    tiles.clear_tile(MAIN_LAYER, 5, 5);
+    tiles.set_tile("missing", 0, 0, STONE);
+    tiles.publish_changes();
+    assert!(tiles.changes().is_empty());
+}
+
+#[test]
+fn fill_covers_the_region_from_either_corner() {
+    let mut tiles = Tiles::default();
+    tiles.fill(MAIN_LAYER, (2, 1), (-1, 0), STONE);
+    let layer = tiles.layer(MAIN_LAYER).unwrap();
+    for x in -2..=3 {
+        for y in -1..=2 {
+            let inside = (-1..=2).contains(&x) && (0..=1).contains(&y);
+            assert_eq!(layer.get(x, y).0 == STONE, inside, "({}, {})", x, y);
+        }
+    }
+    tiles.publish_changes();
+    assert_eq!(tiles.changes().len(), 8);
+
+    tiles.clear_region(MAIN_LAYER, (0, 0), (2, 1));
+    tiles.publish_changes();
+    assert_eq!(tiles.changes().len(), 6 + 2);
+    let layer = tiles.layer(MAIN_LAYER).unwrap();
+    assert_eq!(layer.get(-1, 0), (STONE, 1 << 2));
+    assert_eq!(layer.get(0, 0).0, Tile::NO_TILE);
+}
+
+#[test]
+fn bulk_loads_report_no_changes() {
+    let mut tiles = Tiles::default();
+    tiles.start_recording();
+    for x in 0..4 {
+        tiles.set_autotiled(x, 0, GROUND);
+    }
+    tiles.publish_changes();
+    assert!(tiles.changes().is_empty());
+    assert!(tiles.take_recording().is_empty());
+    assert_eq!(
+        tiles.layer(MAIN_LAYER).unwrap().get(1, 0),
+        (GROUND, 0b10001)
+    );
+}
+
+#[test]
+fn replay_reproduces_recorded_edits() {
+    let mut tiles = Tiles::default();
+    tiles.set_tile(MAIN_LAYER, 9, 9, STONE);
+    tiles.start_recording();
+    tiles.fill(MAIN_LAYER, (0, 0), (3, 2), GROUND);
+    tiles.clear_tile(MAIN_LAYER, 1, 1);
+    tiles.set_tile(FOREGROUND_LAYER, 4, 4, STONE);
+    let recording = tiles.take_recording();
+    assert_eq!(recording.len(), 3);
+    assert!(tiles.take_recording().is_empty());
+
+    let mut replayed = Tiles::default();
+    replayed.replay(&recording);
+    for name in [MAIN_LAYER, FOREGROUND_LAYER] {
+        let (a, b) = (tiles.layer(name).unwrap(), replayed.layer(name).unwrap());
+        for x in -1..=5 {
+            for y in -1..=5 {
+                assert_eq!(a.get(x, y), b.get(x, y), "{} ({}, {})", name, x, y);
+            }
+        }
+    }
+    assert_eq!(
+        replayed.layer(MAIN_LAYER).unwrap().get(9, 9).0,
+        Tile::NO_TILE
+    );
+}
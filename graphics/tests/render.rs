@@ -0,0 +1,115 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::PathBuf;
+
+use asset_server::*;
+use graphics::*;
+
+const TOLERANCE: u8 = 2;
+
+fn manifest_path(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(path)
+}
+
+fn scene() -> (SpriteBatch, TileBatch, TextBatch) {
+    let mut tiles: Tiles = Default::default();
+    let layer = tiles.layer_mut(MAIN_LAYER).unwrap();
+    for x in -6..6 {
+        layer.set(x, -2, (Tile(1), 0));
+    }
+    layer.set(2, -1, (Tile(2), 0));
+
+    let mut sprites: SpriteBatch = Default::default();
+    sprites[Sprite::TestSprite1 as usize].push(SpriteInstance::new(0, 0.0, 0.0));
+    sprites[Sprite::TestSprite2 as usize].push(SpriteInstance {
+        flip_x: true,
+        tint: [1.0, 0.5, 0.5, 1.0],
+        ..SpriteInstance::new(0, -24.0, 8.0)
+    });
+    let text = vec![
+        Text {
+            color: [1.0, 1.0, 0.0, 1.0],
+            ..Text::screen("Score: 42", 2.0, 2.0)
+        },
+        Text {
+            align: Align::Center,
+            wrap: Some(48.0),
+            ..Text::new("centred and wrapped", 0.0, 40.0)
+        },
+    ];
+    (sprites, tiles.batch(), text)
+}
+
+fn check_golden(name: &str, image: &image::RgbaImage) {
+    let path = manifest_path(&format!("tests/golden/{}.png", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        image.save(&path).unwrap();
+        eprintln!("wrote golden image {}", path.display());
+        return;
+    }
+    let golden = match image::open(&path) {
+        Ok(golden) => golden.to_rgba8(),
+        Err(e) => panic!(
+            "can't open golden image {}: {}; run with UPDATE_GOLDEN=1 to create it",
+            path.display(),
+            e
+        ),
+    };
+    assert_eq!(golden.dimensions(), image.dimensions());
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let expected = golden.get_pixel(x, y);
+        for channel in 0..4 {
+            assert!(
+                pixel[channel].abs_diff(expected[channel]) <= TOLERANCE,
+                "{} differs at ({}, {}): {:?} != {:?}",
+                name,
+                x,
+                y,
+                pixel,
+                expected,
+            );
+        }
+    }
+}
+
+#[test]
+fn renders_scene() {
+    let registry = TileRegistry::load(manifest_path("../assets/tiles.ron")).unwrap();
+    let display = Display {
+        virtual_size: (160, 90),
+        ..Default::default()
+    };
+    let assets = AssetServer::new(AssetSource::Directory(manifest_path("../assets")));
+    let mut renderer = match pollster::block_on(Renderer::headless(&registry, assets, display)) {
+        Some(renderer) => renderer,
+        None => {
+            eprintln!("skipping renders_scene: no GPU or software adapter available");
+            return;
+        }
+    };
+    let (sprites, tiles, text) = scene();
+
+    let image = renderer.render_image(&sprites, &tiles, &text, View::new(0.0, 0.0), 0.0);
+    assert_eq!(image.dimensions(), (160, 90));
+    check_golden("scene", &image);
+
+    let zoomed = View {
+        zoom: 2.0,
+        ..View::new(8.0, 0.0)
+    };
+    let image = renderer.render_image(&sprites, &tiles, &text, zoomed, 0.0);
+    check_golden("scene-zoomed", &image);
+}
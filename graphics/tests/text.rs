@@ -0,0 +1,82 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use graphics::*;
+
+fn grid() -> Font {
+    Font {
+        image: String::from("font.png"),
+        cell: (6, 8),
+        columns: 16,
+        first: ' ',
+        count: 95,
+        line_height: 9,
+    }
+}
+
+#[test]
+fn glyphs_map_to_grid_cells() {
+    let font = grid();
+    assert_eq!(font.rows(), 6);
+    assert_eq!(font.glyph(' '), Some((0, 0)));
+    assert_eq!(font.glyph('A'), Some((1, 2)));
+    assert_eq!(font.glyph('~'), Some((14, 5)));
+    assert_eq!(font.glyph('\u{7f}'), None);
+}
+
+#[test]
+fn wraps_at_word_boundaries() {
+    let font = grid();
+    assert_eq!(
+        font.lines("the quick brown fox", Some(60.0)),
+        ["the quick", "brown fox"]
+    );
+    assert_eq!(font.lines("a\nb  c", None), ["a", "b  c"]);
+    assert_eq!(font.lines("abcdefgh", Some(18.0)), ["abc", "def", "gh"]);
+}
+
+#[test]
+fn alignment_offsets_lines() {
+    let font = grid();
+    let text = |align| Text {
+        align,
+        scale: 2.0,
+        ..Text::new("ab\nc", 0.0, 0.0)
+    };
+    let xs = |align| -> Vec<(f32, f32)> {
+        font.layout(&text(align))
+            .iter()
+            .map(|glyph| (glyph.x, glyph.y))
+            .collect()
+    };
+    assert_eq!(xs(Align::Left), [(0.0, 0.0), (12.0, 0.0), (0.0, 18.0)]);
+    assert_eq!(xs(Align::Center), [(-12.0, 0.0), (0.0, 0.0), (-6.0, 18.0)]);
+    assert_eq!(
+        xs(Align::Right),
+        [(-24.0, 0.0), (-12.0, 0.0), (-12.0, 18.0)]
+    );
+}
+
+#[test]
+fn unknown_characters_fall_back() {
+    let font = grid();
+    let glyphs = font.layout(&Text::new("é", 0.0, 0.0));
+    assert_eq!((glyphs[0].column, glyphs[0].row), font.glyph('?').unwrap());
+}
+
+#[test]
+fn embedded_font_loads() {
+    let font = graphics::font();
+    assert!((' '..='~').all(|c| font.glyph(c).is_some()));
+}
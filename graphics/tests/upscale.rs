@@ -0,0 +1,57 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use graphics::*;
+
+fn display(upscale: Upscale) -> Display {
+    Display {
+        virtual_size: (320, 180),
+        upscale,
+    }
+}
+
+#[test]
+fn integer_scale_rounds_down_and_centres() {
+    let letterbox = display(Upscale::Integer).letterbox((1366, 768));
+    assert_eq!(letterbox, (43.0, 24.0, 1280.0, 720.0, 4.0));
+}
+
+#[test]
+fn integer_scale_fills_exact_multiples() {
+    let letterbox = display(Upscale::Integer).letterbox((1920, 1080));
+    assert_eq!(letterbox, (0.0, 0.0, 1920.0, 1080.0, 6.0));
+}
+
+#[test]
+fn sharp_bilinear_fills_the_limiting_axis() {
+    let letterbox = display(Upscale::SharpBilinear).letterbox((1366, 768));
+    let scale = 768.0 / 180.0;
+    assert_eq!(letterbox, (0.0, 0.0, 320.0 * scale, 768.0, scale));
+}
+
+#[test]
+fn windows_smaller_than_the_virtual_size_scale_down() {
+    for upscale in [Upscale::Integer, Upscale::SharpBilinear] {
+        let letterbox = display(upscale).letterbox((160, 120));
+        assert_eq!(letterbox, (0.0, 15.0, 160.0, 90.0, 0.5));
+    }
+}
+
+#[test]
+fn odd_sizes_floor_the_offset() {
+    let letterbox = display(Upscale::Integer).letterbox((641, 361));
+    assert_eq!(letterbox, (0.0, 0.0, 640.0, 360.0, 2.0));
+    let letterbox = display(Upscale::Integer).letterbox((963, 545));
+    assert_eq!(letterbox, (1.0, 2.0, 960.0, 540.0, 3.0));
+}
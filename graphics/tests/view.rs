@@ -0,0 +1,58 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use graphics::*;
+
+// The camera part of vs_main in shader.wgsl, without the pixel snapping,
+// returning a y-down offset from the centre of the screen.
+fn world_to_screen(view: &View, x: f32, y: f32) -> (f32, f32) {
+    let (rx, ry) = ((x - view.x) * view.zoom, (y - view.y) * view.zoom);
+    let (cs, cc) = (-view.rotation).sin_cos();
+    (rx * cc - ry * cs, -(rx * cs + ry * cc))
+}
+
+#[test]
+fn screen_to_world_inverts_shader() {
+    for rotation in [0.0, 0.3, -1.2, std::f32::consts::FRAC_PI_2, 3.0] {
+        for zoom in [0.5, 1.0, 3.0] {
+            let view = View {
+                zoom,
+                rotation,
+                ..View::new(12.0, -7.0)
+            };
+            for (x, y) in [(0.0, 0.0), (20.0, 5.0), (-33.0, 41.0)] {
+                let (sx, sy) = world_to_screen(&view, x, y);
+                let (wx, wy) = view.screen_to_world(sx, sy);
+                assert!(
+                    (wx - x).abs() < 0.001 && (wy - y).abs() < 0.001,
+                    "{:?}: ({}, {}) came back as ({}, {})",
+                    view,
+                    x,
+                    y,
+                    wx,
+                    wy
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn screen_up_is_world_up_without_rotation() {
+    let view = View {
+        zoom: 2.0,
+        ..View::new(5.0, 5.0)
+    };
+    assert_eq!(view.screen_to_world(4.0, -4.0), (7.0, 7.0));
+}
@@ -12,45 +12,135 @@
  * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
  */
 
+extern crate asset_server;
+extern crate audio;
 extern crate ecs;
 extern crate graphics;
+extern crate tiled_import;
 
 fn main() {
     let mut world = ecs::World::new();
 
+    world.systems.push(Box::new(
+        ecs::kinematic_system
+            as fn(&mut ecs::Timer, &mut ecs::Solids, &mut ecs::AABB, &mut ecs::Kinematic),
+    ));
+    world.systems.push(Box::new(
+        ecs::physics_system
+            as fn(
+                &mut ecs::Timer,
+                &mut f32,
+                &mut graphics::Tiles,
+                &mut ecs::AABB,
+                &mut ecs::Velocity,
+                &mut ecs::PhysicsBody,
+            ),
+    ));
     world.systems.push(Box::new(
         ecs::update_aabb
-            as fn(&mut ecs::Timer, &mut graphics::Tiles, &mut ecs::AABB, &mut ecs::Velocity),
+            as fn(
+                &mut ecs::Timer,
+                &mut graphics::Tiles,
+                &mut ecs::Solids,
+                &mut ecs::AABB,
+                &mut ecs::Velocity,
+            ),
     ));
-    world
-        .systems
-        .push(Box::new(ecs::print_fps as fn(&mut ecs::Timer)));
     world.systems.push(Box::new(
-        ecs::render_sprite as fn(&mut ecs::SpriteBatchRes, &mut ecs::AABB, &mut ecs::Sprite),
+        ecs::show_fps as fn(&mut ecs::Timer, &mut ecs::TextBatchRes),
     ));
     world.systems.push(Box::new(
-        ecs::player_system
+        ecs::platformer_system
             as fn(
                 &mut ecs::Timer,
                 &mut graphics::GameInput,
-                &mut (f32, f32),
+                &mut graphics::Tiles,
+                &mut ecs::AABB,
+                &mut ecs::Velocity,
+                &mut ecs::PhysicsBody,
+                &mut ecs::PlatformerController,
+            ),
+    ));
+    world.systems.push(Box::new(
+        ecs::hazard_system
+            as fn(
+                &mut ecs::Timer,
+                &mut graphics::Tiles,
+                &mut ecs::Camera,
+                &mut ecs::AABB,
+                &mut ecs::Health,
+            ),
+    ));
+    world.systems.push(Box::new(
+        ecs::player_system
+            as fn(
+                &mut ecs::Camera,
                 &mut (f32, f32),
                 &mut ecs::AABB,
                 &mut ecs::Velocity,
                 &mut ecs::Sprite,
+                &mut ecs::Animator,
+                &mut ecs::PlatformerController,
                 &mut ecs::Player,
             ),
     ));
+    world.systems.push(Box::new(
+        ecs::animation_system as fn(&mut ecs::Timer, &mut ecs::Sprite, &mut ecs::Animator),
+    ));
+    world.systems.push(Box::new(
+        ecs::render_sprite as fn(&mut ecs::SpriteBatchRes, &mut ecs::AABB, &mut ecs::Sprite),
+    ));
 
-    let scene: (graphics::Tiles, Vec<ecs::EntityDesc>) =
-        bincode::deserialize(&std::fs::read("assets/testscene.bin").unwrap()).unwrap();
+    #[cfg(feature = "embed-assets")]
+    let tile_registry = graphics::TileRegistry::parse(include_str!("../assets/tiles.ron")).unwrap();
+    #[cfg(not(feature = "embed-assets"))]
+    let tile_registry = graphics::TileRegistry::load("assets/tiles.ron").unwrap();
 
-    world.resources.tiles = scene.0;
-    for entity in scene.1 {
-        entity.construct(&mut world);
+    let scene_path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| String::from("assets/testscene.bin"));
+    if std::path::Path::new(&scene_path).is_dir() {
+        ecs::Streamer::open(&mut world, &scene_path, Default::default()).unwrap();
+    } else {
+        let scene: (graphics::Tiles, Vec<ecs::EntityDesc>) =
+            if scene_path.ends_with(".tmx") || scene_path.ends_with(".tmj") {
+                let mapping = tiled_import::TiledMapping::load("assets/tiled.ron").unwrap();
+                tiled_import::load(&scene_path, &mapping, &tile_registry).unwrap()
+            } else {
+                bincode::deserialize(&std::fs::read(&scene_path).unwrap()).unwrap()
+            };
+        world.resources.tiles = scene.0;
+        for entity in scene.1 {
+            entity.construct(&mut world);
+        }
+        world.resources.camera.clamp_to(&world.resources.tiles);
     }
+    world.resources.tiles.apply_registry(&tile_registry);
+
+    #[cfg(feature = "embed-assets")]
+    let assets = asset_server::embed_assets!(
+        "../assets",
+        "gen/test-sprite1.png",
+        "gen/test-sprite2.png",
+        "gen/font.png",
+        "gen/test-tileset1.png",
+        "gen/test-tileset2.png",
+        "gen/test-sound1.wav",
+    );
+    #[cfg(not(feature = "embed-assets"))]
+    let assets = asset_server::AssetSource::Directory("assets".into());
+
+    world.resources.sounds =
+        Some(audio::Sounds::new(asset_server::AssetServer::new(assets.clone())).unwrap());
 
-    pollster::block_on(graphics::Graphics::new()).run(move |controller, p_cx, p_cy, p_ax, p_ay| {
-        world.run(controller.get_game_input(p_cx, p_cy, p_ax, p_ay))
+    pollster::block_on(graphics::Graphics::new(
+        &tile_registry,
+        asset_server::AssetServer::new(assets),
+    ))
+    .run(move |controller, p_view, p_ax, p_ay| {
+        world.run(
+            controller.get_game_input(&p_view, p_ax, p_ay),
+            controller.viewport,
+        )
     });
 }
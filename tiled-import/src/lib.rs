@@ -0,0 +1,251 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+pub mod mapping;
+pub mod tmj;
+pub mod tmx;
+
+pub use mapping::*;
+
+use std::collections::HashMap;
+use std::io::prelude::*;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+extern crate ecs;
+extern crate graphics;
+
+const GID_MASK: u32 = 0x0fffffff;
+
+pub struct TiledMap {
+    pub width: u32,
+    pub height: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub infinite: bool,
+    pub tilesets: Vec<(u32, String)>,
+    pub layers: Vec<TiledLayer>,
+}
+
+pub enum TiledLayer {
+    Tiles {
+        name: String,
+        chunks: Vec<TiledChunk>,
+    },
+    Objects {
+        name: String,
+        objects: Vec<TiledObject>,
+    },
+}
+
+pub struct TiledChunk {
+    pub x: i64,
+    pub y: i64,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u32>,
+}
+
+pub struct TiledObject {
+    pub name: String,
+    pub class: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub properties: HashMap<String, String>,
+}
+
+fn invalid<E: std::fmt::Display>(e: E) -> Error {
+    Error::new(ErrorKind::InvalidData, e.to_string())
+}
+
+pub fn tileset_key(source: &str) -> String {
+    Path::new(source)
+        .file_stem()
+        .map_or(source, |stem| stem.to_str().unwrap_or(source))
+        .to_string()
+}
+
+pub fn decode_data(
+    text: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> std::io::Result<Vec<u32>> {
+    match encoding {
+        Some("csv") => text
+            .split(',')
+            .map(str::trim)
+            .filter(|gid| !gid.is_empty())
+            .map(|gid| gid.parse::<u32>().map_err(invalid))
+            .collect(),
+        Some("base64") => {
+            let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+            let bytes = base64::decode(text).map_err(invalid)?;
+            let mut data = Vec::new();
+            match compression {
+                None | Some("") => data = bytes,
+                Some("zlib") => {
+                    flate2::read::ZlibDecoder::new(&bytes[..]).read_to_end(&mut data)?;
+                }
+                Some("gzip") => {
+                    flate2::read::GzDecoder::new(&bytes[..]).read_to_end(&mut data)?;
+                }
+                Some(other) => {
+                    return Err(invalid(format!("Unsupported compression {}.", other)));
+                }
+            }
+            Ok(data
+                .chunks_exact(4)
+                .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+                .collect())
+        }
+        Some(other) => Err(invalid(format!("Unsupported encoding {}.", other))),
+        None => Err(invalid(
+            "Tile data without an encoding must be given as <tile gid=\"...\"/> elements.",
+        )),
+    }
+}
+
+pub fn load_map<P: AsRef<Path>>(path: P) -> std::io::Result<TiledMap> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("tmx") => tmx::parse(&text),
+        Some("tmj") | Some("json") => tmj::parse(&text),
+        _ => Err(invalid(format!("{} is not a Tiled map.", path.display()))),
+    }
+}
+
+fn property(object: &TiledObject, name: &str, default: f32) -> std::io::Result<f32> {
+    match object.properties.get(name) {
+        Some(value) => value.parse().map_err(|_| {
+            invalid(format!(
+                "Object {} has a non-numeric {} property.",
+                object.name, name
+            ))
+        }),
+        None => Ok(default),
+    }
+}
+
+pub fn convert(
+    map: &TiledMap,
+    mapping: &TiledMapping,
+    registry: &graphics::TileRegistry,
+) -> std::io::Result<(graphics::Tiles, Vec<ecs::EntityDesc>)> {
+    let mut tiles: graphics::Tiles = Default::default();
+    tiles.apply_registry(registry);
+    let mut entities = Vec::new();
+
+    let scale_x = graphics::TILE_SIZE as f32 / map.tile_width as f32;
+    let scale_y = graphics::TILE_SIZE as f32 / map.tile_height as f32;
+    let rows = if map.infinite { 0 } else { map.height as i64 };
+
+    for layer in map.layers.iter() {
+        match layer {
+            TiledLayer::Tiles { name, chunks } => {
+                let target = mapping.layer(name);
+                if tiles.layer(target).is_none() {
+                    return Err(invalid(format!("Layer {} has no target layer.", name)));
+                }
+                for chunk in chunks.iter() {
+                    if chunk.width == 0
+                        || chunk.data.len() as u64 != chunk.width as u64 * chunk.height as u64
+                    {
+                        return Err(invalid(format!(
+                            "Chunk at {}, {} of layer {} has {} tiles for a {}x{} area.",
+                            chunk.x,
+                            chunk.y,
+                            name,
+                            chunk.data.len(),
+                            chunk.width,
+                            chunk.height
+                        )));
+                    }
+                    for (i, gid) in chunk.data.iter().enumerate() {
+                        let x = chunk.x + (i as u32 % chunk.width) as i64;
+                        let y = chunk.y + (i as u32 / chunk.width) as i64;
+                        if gid & !GID_MASK != 0 {
+                            return Err(invalid(format!(
+                                "Tile at {}, {} of layer {} is flipped or rotated.",
+                                x, y, name
+                            )));
+                        }
+                        let gid = *gid;
+                        if gid == 0 {
+                            continue;
+                        }
+                        let (first_gid, key) = map
+                            .tilesets
+                            .iter()
+                            .filter(|(first_gid, _)| *first_gid <= gid)
+                            .max_by_key(|(first_gid, _)| *first_gid)
+                            .ok_or_else(|| invalid(format!("Tile {} has no tileset.", gid)))?;
+                        let local = gid - first_gid;
+                        let tile_name = mapping
+                            .tilesets
+                            .get(key)
+                            .and_then(|tileset| tileset.get(&local))
+                            .ok_or_else(|| {
+                                invalid(format!("Tile {} of tileset {} is not mapped.", local, key))
+                            })?;
+                        let tile = registry.by_name(tile_name).ok_or_else(|| {
+                            invalid(format!("Tile {} is not in the registry.", tile_name))
+                        })?;
+                        tiles.set_autotiled_in(target, x, rows - 1 - y, tile);
+                    }
+                }
+            }
+            TiledLayer::Objects { objects, .. } => {
+                for object in objects.iter() {
+                    let x = (object.x + object.width / 2.0) * scale_x;
+                    let y = (rows as f32 * map.tile_height as f32 - object.y - object.height / 2.0)
+                        * scale_y;
+                    match mapping.object(&object.class) {
+                        "" => {}
+                        "Player" => {
+                            entities.push(ecs::EntityDesc::Player(ecs::PlayerDesc { x, y }))
+                        }
+                        "Platform" => entities.push(ecs::EntityDesc::Platform(ecs::PlatformDesc {
+                            x,
+                            y,
+                            w: object.width * scale_x,
+                            h: object.height * scale_y,
+                            dx: property(object, "dx", 0.0)? * scale_x,
+                            dy: -property(object, "dy", 0.0)? * scale_y,
+                            period: property(object, "period", 1.0)?,
+                        })),
+                        other => {
+                            return Err(invalid(format!(
+                                "Object {} has unknown class {}.",
+                                object.name, other
+                            )))
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok((tiles, entities))
+}
+
+pub fn load<P: AsRef<Path>>(
+    path: P,
+    mapping: &TiledMapping,
+    registry: &graphics::TileRegistry,
+) -> std::io::Result<(graphics::Tiles, Vec<ecs::EntityDesc>)> {
+    convert(&load_map(path)?, mapping, registry)
+}
@@ -0,0 +1,51 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::env::*;
+use std::fs::File;
+use std::io::prelude::*;
+
+extern crate ecs;
+extern crate graphics;
+extern crate tiled_import;
+
+fn main() {
+    let mut args: Vec<String> = args().collect();
+    let streamed = args.iter().any(|arg| arg == "--streamed");
+    args.retain(|arg| arg != "--streamed");
+    if args.len() < 4 {
+        eprintln!(
+            "Usage: tiled-import [--streamed] <map.tmx|map.tmj> <mapping.ron> <scene.bin|world-dir> [tiles.ron]"
+        );
+        std::process::exit(1);
+    }
+    let registry_path = args.get(4).map_or("assets/tiles.ron", String::as_str);
+
+    let registry = graphics::TileRegistry::load(registry_path)
+        .unwrap_or_else(|e| panic!("Couldn't load {}: {}.", registry_path, e));
+    let mapping = tiled_import::TiledMapping::load(&args[2])
+        .unwrap_or_else(|e| panic!("Couldn't load {}: {}.", args[2], e));
+    let (mut tiles, entities) = tiled_import::load(&args[1], &mapping, &registry)
+        .unwrap_or_else(|e| panic!("Couldn't import {}: {}.", args[1], e));
+
+    tiles.compact();
+    if streamed {
+        ecs::save_streamed(&args[3], &tiles, &entities)
+            .unwrap_or_else(|e| panic!("Couldn't write {}: {}.", args[3], e));
+    } else {
+        let serialized = bincode::serialize(&(tiles, entities)).unwrap();
+        let mut file = File::create(&args[3]).unwrap();
+        file.write_all(&serialized).unwrap();
+    }
+}
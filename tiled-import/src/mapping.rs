@@ -0,0 +1,43 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TiledMapping {
+    pub tilesets: HashMap<String, HashMap<u32, String>>,
+    #[serde(default)]
+    pub layers: HashMap<String, String>,
+    #[serde(default)]
+    pub objects: HashMap<String, String>,
+}
+
+impl TiledMapping {
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        ron::from_str(&text).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    pub fn layer<'a>(&'a self, name: &'a str) -> &'a str {
+        self.layers.get(name).map_or(name, String::as_str)
+    }
+
+    pub fn object<'a>(&'a self, class: &'a str) -> &'a str {
+        self.objects.get(class).map_or(class, String::as_str)
+    }
+}
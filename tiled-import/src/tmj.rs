@@ -0,0 +1,236 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+
+use serde::Deserialize;
+
+use super::*;
+
+#[derive(Deserialize)]
+struct Map {
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default = "orthogonal")]
+    orientation: String,
+    #[serde(default)]
+    tilesets: Vec<Tileset>,
+    #[serde(default)]
+    layers: Vec<Layer>,
+}
+
+fn orthogonal() -> String {
+    String::from("orthogonal")
+}
+
+#[derive(Deserialize)]
+struct Tileset {
+    firstgid: u32,
+    source: Option<String>,
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Data {
+    Csv(Vec<u32>),
+    Base64(String),
+}
+
+#[derive(Deserialize)]
+struct Chunk {
+    x: i64,
+    y: i64,
+    width: u32,
+    height: u32,
+    data: Data,
+}
+
+#[derive(Deserialize)]
+struct Property {
+    name: String,
+    value: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct Object {
+    #[serde(default)]
+    name: String,
+    #[serde(default, alias = "type")]
+    class: String,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    properties: Vec<Property>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum Layer {
+    #[serde(rename = "tilelayer")]
+    Tiles {
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        width: u32,
+        #[serde(default)]
+        height: u32,
+        data: Option<Data>,
+        chunks: Option<Vec<Chunk>>,
+        encoding: Option<String>,
+        compression: Option<String>,
+    },
+    #[serde(rename = "objectgroup")]
+    Objects {
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        objects: Vec<Object>,
+    },
+    #[serde(rename = "group")]
+    Group {
+        #[serde(default)]
+        layers: Vec<Layer>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+fn decode(
+    data: &Data,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> std::io::Result<Vec<u32>> {
+    match data {
+        Data::Csv(gids) => Ok(gids.clone()),
+        Data::Base64(text) => decode_data(text, Some(encoding.unwrap_or("base64")), compression),
+    }
+}
+
+fn convert_layers(layers: &[Layer], out: &mut Vec<TiledLayer>) -> std::io::Result<()> {
+    for layer in layers.iter() {
+        match layer {
+            Layer::Tiles {
+                name,
+                width,
+                height,
+                data,
+                chunks,
+                encoding,
+                compression,
+            } => {
+                let encoding = encoding.as_deref();
+                let compression = compression.as_deref();
+                let mut tiled_chunks = Vec::new();
+                for chunk in chunks.iter().flatten() {
+                    tiled_chunks.push(TiledChunk {
+                        x: chunk.x,
+                        y: chunk.y,
+                        width: chunk.width,
+                        height: chunk.height,
+                        data: decode(&chunk.data, encoding, compression)?,
+                    });
+                }
+                if let Some(data) = data {
+                    tiled_chunks.push(TiledChunk {
+                        x: 0,
+                        y: 0,
+                        width: *width,
+                        height: *height,
+                        data: decode(data, encoding, compression)?,
+                    });
+                }
+                out.push(TiledLayer::Tiles {
+                    name: name.clone(),
+                    chunks: tiled_chunks,
+                });
+            }
+            Layer::Objects { name, objects } => out.push(TiledLayer::Objects {
+                name: name.clone(),
+                objects: objects
+                    .iter()
+                    .map(|object| TiledObject {
+                        name: object.name.clone(),
+                        class: object.class.clone(),
+                        x: object.x,
+                        y: object.y,
+                        width: object.width,
+                        height: object.height,
+                        properties: object
+                            .properties
+                            .iter()
+                            .map(|property| {
+                                let value = match &property.value {
+                                    serde_json::Value::String(value) => value.clone(),
+                                    value => value.to_string(),
+                                };
+                                (property.name.clone(), value)
+                            })
+                            .collect::<HashMap<_, _>>(),
+                    })
+                    .collect(),
+            }),
+            Layer::Group { layers } => convert_layers(layers, out)?,
+            Layer::Other => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn parse(text: &str) -> std::io::Result<TiledMap> {
+    let map: Map = serde_json::from_str(text).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    if map.orientation != "orthogonal" {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Only orthogonal maps are supported.",
+        ));
+    }
+
+    let mut tilesets = Vec::new();
+    for tileset in map.tilesets.iter() {
+        let key = match (&tileset.source, &tileset.name) {
+            (Some(source), _) => tileset_key(source),
+            (None, Some(name)) => name.clone(),
+            (None, None) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Tileset without a name.",
+                ))
+            }
+        };
+        tilesets.push((tileset.firstgid, key));
+    }
+
+    let mut layers = Vec::new();
+    convert_layers(&map.layers, &mut layers)?;
+
+    Ok(TiledMap {
+        width: map.width,
+        height: map.height,
+        tile_width: map.tilewidth,
+        tile_height: map.tileheight,
+        infinite: map.infinite,
+        tilesets,
+        layers,
+    })
+}
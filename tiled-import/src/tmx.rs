@@ -0,0 +1,174 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+
+use roxmltree::{Document, Node};
+
+use super::*;
+
+fn attr<T: std::str::FromStr>(node: &Node, name: &str) -> std::io::Result<T> {
+    node.attribute(name)
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "<{}> has a missing or invalid {}.",
+                    node.tag_name().name(),
+                    name
+                ),
+            )
+        })
+}
+
+fn attr_or<T: std::str::FromStr>(node: &Node, name: &str, default: T) -> T {
+    node.attribute(name)
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+// Reads the gids of a <data> or <chunk> element, which are either encoded
+// text or, without an encoding, one <tile gid="..."/> element per cell.
+fn parse_data(
+    node: &Node,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> std::io::Result<Vec<u32>> {
+    if encoding.is_none() {
+        return Ok(node
+            .children()
+            .filter(|child| child.has_tag_name("tile"))
+            .map(|tile| attr_or(&tile, "gid", 0))
+            .collect());
+    }
+    decode_data(node.text().unwrap_or(""), encoding, compression)
+}
+
+fn parse_layer(node: &Node) -> std::io::Result<TiledLayer> {
+    let data = node
+        .children()
+        .find(|child| child.has_tag_name("data"))
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Tile layer without <data>."))?;
+    let encoding = data.attribute("encoding");
+    let compression = data.attribute("compression");
+    let mut chunks = Vec::new();
+    for chunk in data.children().filter(|child| child.has_tag_name("chunk")) {
+        chunks.push(TiledChunk {
+            x: attr(&chunk, "x")?,
+            y: attr(&chunk, "y")?,
+            width: attr(&chunk, "width")?,
+            height: attr(&chunk, "height")?,
+            data: parse_data(&chunk, encoding, compression)?,
+        });
+    }
+    if chunks.is_empty() {
+        chunks.push(TiledChunk {
+            x: 0,
+            y: 0,
+            width: attr(node, "width")?,
+            height: attr(node, "height")?,
+            data: parse_data(&data, encoding, compression)?,
+        });
+    }
+    Ok(TiledLayer::Tiles {
+        name: attr_or(node, "name", String::new()),
+        chunks,
+    })
+}
+
+fn parse_objects(node: &Node) -> std::io::Result<TiledLayer> {
+    let mut objects = Vec::new();
+    for object in node.children().filter(|child| child.has_tag_name("object")) {
+        let mut properties = HashMap::new();
+        for property in object
+            .children()
+            .filter(|child| child.has_tag_name("properties"))
+            .flat_map(|properties| properties.children())
+            .filter(|child| child.has_tag_name("property"))
+        {
+            let value = property
+                .attribute("value")
+                .or_else(|| property.text())
+                .unwrap_or("");
+            properties.insert(attr(&property, "name")?, String::from(value));
+        }
+        objects.push(TiledObject {
+            name: attr_or(&object, "name", String::new()),
+            class: object
+                .attribute("class")
+                .or_else(|| object.attribute("type"))
+                .map(String::from)
+                .unwrap_or_default(),
+            x: attr(&object, "x")?,
+            y: attr(&object, "y")?,
+            width: attr_or(&object, "width", 0.0),
+            height: attr_or(&object, "height", 0.0),
+            properties,
+        });
+    }
+    Ok(TiledLayer::Objects {
+        name: attr_or(node, "name", String::new()),
+        objects,
+    })
+}
+
+fn parse_layers(node: &Node, layers: &mut Vec<TiledLayer>) -> std::io::Result<()> {
+    for child in node.children() {
+        match child.tag_name().name() {
+            "layer" => layers.push(parse_layer(&child)?),
+            "objectgroup" => layers.push(parse_objects(&child)?),
+            "group" => parse_layers(&child, layers)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn parse(text: &str) -> std::io::Result<TiledMap> {
+    let document = Document::parse(text).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let map = document.root_element();
+    if !map.has_tag_name("map") {
+        return Err(Error::new(ErrorKind::InvalidData, "Missing <map> element."));
+    }
+    if attr_or(&map, "orientation", String::from("orthogonal")) != "orthogonal" {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Only orthogonal maps are supported.",
+        ));
+    }
+
+    let mut tilesets = Vec::new();
+    for tileset in map.children().filter(|child| child.has_tag_name("tileset")) {
+        let key = match tileset.attribute("source") {
+            Some(source) => tileset_key(source),
+            None => attr(&tileset, "name")?,
+        };
+        tilesets.push((attr(&tileset, "firstgid")?, key));
+    }
+
+    let mut layers = Vec::new();
+    parse_layers(&map, &mut layers)?;
+
+    Ok(TiledMap {
+        width: attr(&map, "width")?,
+        height: attr(&map, "height")?,
+        tile_width: attr(&map, "tilewidth")?,
+        tile_height: attr(&map, "tileheight")?,
+        infinite: attr_or(&map, "infinite", 0) != 0,
+        tilesets,
+        layers,
+    })
+}
@@ -0,0 +1,123 @@
+/*
+ * This file is part of game-testbed.
+ * game-testbed is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * any later version.
+ * game-testbed is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * You should have received a copy of the GNU General Public License
+ * along with game-testbed. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::PathBuf;
+
+use graphics::{Tile, TileRegistry, Tiles, MAIN_LAYER};
+use tiled_import::*;
+
+const GIDS: [u32; 6] = [1, 0, 2, 0, 0, 1];
+
+fn manifest_path(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(path)
+}
+
+fn import(name: &str) -> std::io::Result<(Tiles, TileRegistry)> {
+    let registry = TileRegistry::load(manifest_path("../assets/tiles.ron")).unwrap();
+    let mapping = TiledMapping::load(manifest_path("../assets/tiled.ron")).unwrap();
+    let path = manifest_path(&format!("tests/maps/{}", name));
+    let (tiles, _) = load(path, &mapping, &registry)?;
+    Ok((tiles, registry))
+}
+
+fn gids(name: &str) -> Vec<u32> {
+    let map = load_map(manifest_path(&format!("tests/maps/{}", name))).unwrap();
+    match &map.layers[0] {
+        TiledLayer::Tiles { chunks, .. } => chunks[0].data.clone(),
+        TiledLayer::Objects { .. } => panic!("{} has no tile layer", name),
+    }
+}
+
+fn tile(tiles: &Tiles, x: i64, y: i64) -> Tile {
+    tiles.layer(MAIN_LAYER).unwrap().get(x, y).0
+}
+
+#[test]
+fn decodes_every_encoding() {
+    for name in [
+        "csv.tmx",
+        "base64.tmx",
+        "zlib.tmx",
+        "gzip.tmx",
+        "xml.tmx",
+        "csv.tmj",
+        "zlib.tmj",
+    ] {
+        assert_eq!(gids(name), GIDS, "{}", name);
+    }
+}
+
+#[test]
+fn rejects_unknown_compression() {
+    assert!(decode_data("AAAAAA==", Some("base64"), Some("zstd")).is_err());
+    assert!(decode_data("1,2", Some("xml"), None).is_err());
+    let error = decode_data("1,2", None, None).err().unwrap();
+    assert!(error.to_string().contains("<tile"), "{}", error);
+}
+
+#[test]
+fn flips_rows_to_y_up() {
+    let (tiles, registry) = import("csv.tmx").unwrap();
+    let tile1 = registry.by_name("test-tile1").unwrap();
+    let tile2 = registry.by_name("test-tile2").unwrap();
+    // Tiled row 0 is the top row, which is y = 1 in a two-row map.
+    assert_eq!(tile(&tiles, 0, 1), tile1);
+    assert_eq!(tile(&tiles, 1, 1), Tile::NO_TILE);
+    assert_eq!(tile(&tiles, 2, 1), tile2);
+    assert_eq!(tile(&tiles, 0, 0), Tile::NO_TILE);
+    assert_eq!(tile(&tiles, 2, 0), tile1);
+}
+
+#[test]
+fn resolves_gids_against_the_nearest_first_gid() {
+    let (tiles, registry) = import("tilesets.tmx").unwrap();
+    assert_eq!(tile(&tiles, 0, 1), registry.by_name("test-tile1").unwrap());
+    assert_eq!(tile(&tiles, 2, 1), registry.by_name("test-tile2").unwrap());
+    assert_eq!(tile(&tiles, 2, 0), registry.by_name("test-tile1").unwrap());
+}
+
+#[test]
+fn rejects_chunks_that_do_not_match_their_size() {
+    let registry = TileRegistry::load(manifest_path("../assets/tiles.ron")).unwrap();
+    let mapping = TiledMapping::load(manifest_path("../assets/tiled.ron")).unwrap();
+    for (width, height, data) in [(0, 2, vec![1, 1]), (3, 2, vec![1, 0, 2, 0])] {
+        let map = TiledMap {
+            width,
+            height,
+            tile_width: 16,
+            tile_height: 16,
+            infinite: false,
+            tilesets: vec![(1, String::from("testbed"))],
+            layers: vec![TiledLayer::Tiles {
+                name: String::from("Ground"),
+                chunks: vec![TiledChunk {
+                    x: 0,
+                    y: 0,
+                    width,
+                    height,
+                    data,
+                }],
+            }],
+        };
+        let error = convert(&map, &mapping, &registry).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn rejects_flipped_tiles() {
+    assert_eq!(gids("flipped.tmx")[2], 0x80000002);
+    let error = import("flipped.tmx").err().unwrap();
+    assert!(error.to_string().contains("flipped"), "{}", error);
+}